@@ -1,12 +1,105 @@
-#![feature(test)]
-extern crate test;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use html_editor::operation::{Htmlifiable, Queryable, Selector};
+use html_editor::{parse, Node};
 
-use html_editor::parse;
-use test::Bencher;
+const HTML: &str = include_str!("./bench.html");
 
-#[bench]
-fn bench(b: &mut Bencher) {
-    b.iter(|| {
-        parse(include_str!("./bench.html")).unwrap();
-    })
+fn bench_parse(c: &mut Criterion) {
+    c.bench_function("parse", |b| b.iter(|| parse(HTML).unwrap()));
 }
+
+fn bench_html(c: &mut Criterion) {
+    let nodes = parse(HTML).unwrap();
+    c.bench_function("html", |b| b.iter(|| nodes.html()));
+}
+
+fn bench_query_all(c: &mut Criterion) {
+    let nodes = parse(HTML).unwrap();
+    let mut group = c.benchmark_group("query_all");
+    // `:is(#content)` matches the same elements, but goes through the
+    // generic path which visits the whole document.
+    for source in ["#content", ":is(#content)", ".mw-headline"] {
+        let selector = Selector::from(source);
+        group.bench_function(source, |b| b.iter(|| nodes.query_all(&selector)));
+    }
+    group.finish();
+}
+
+// A long text, once without any entity and once made of entities.
+fn entity_texts() -> [(&'static str, String); 2] {
+    [
+        (
+            "entity-free",
+            "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(100),
+        ),
+        (
+            "entity-heavy",
+            "&lt;a href=&quot;?a=1&amp;b=2&quot;&gt;&copy; &#169;&lt;/a&gt; ".repeat(100),
+        ),
+    ]
+}
+
+// `checked` scans the text before calling `html_escape`, as the parser and
+// the serializer do, while `unchecked` always calls it, as they did before.
+// The whole documents are benchmarked too, with the text in an attribute.
+
+fn bench_decode_entities(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_entities");
+    for (name, text) in entity_texts() {
+        group.bench_with_input(BenchmarkId::new("checked", name), &text, |b, text| {
+            b.iter(|| {
+                if text.contains('&') {
+                    html_escape::decode_html_entities(text).into_owned()
+                } else {
+                    text.to_string()
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("unchecked", name), &text, |b, text| {
+            b.iter(|| html_escape::decode_html_entities(text).into_owned())
+        });
+        let html = format!(r#"<p title="{0}">{0}</p>"#, text);
+        group.bench_with_input(BenchmarkId::new("parse", name), &html, |b, html| {
+            b.iter(|| parse(html).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_encode_text(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode_text");
+    for (name, text) in entity_texts() {
+        let text = html_escape::decode_html_entities(&text).into_owned();
+        group.bench_with_input(BenchmarkId::new("checked", name), &text, |b, text| {
+            b.iter(|| {
+                if text.contains(['&', '<', '>']) {
+                    html_escape::encode_text(text).into_owned()
+                } else {
+                    text.to_string()
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("unchecked", name), &text, |b, text| {
+            b.iter(|| html_escape::encode_text(text).into_owned())
+        });
+        let nodes = vec![Node::new_element(
+            "p",
+            vec![("title", &text)],
+            vec![Node::Text(text.clone())],
+        )];
+        group.bench_with_input(BenchmarkId::new("html", name), &nodes, |b, nodes| {
+            b.iter(|| nodes.html())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_parse,
+    bench_html,
+    bench_query_all,
+    bench_decode_entities,
+    bench_encode_text
+);
+criterion_main!(benches);
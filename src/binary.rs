@@ -0,0 +1,259 @@
+//! A compact binary format for the DOM, to cache parsed documents without
+//! parsing them again. It requires the `binary` feature.
+//!
+//! ```
+//! use html_editor::parse;
+//! use html_editor::binary::{from_bytes, to_bytes};
+//! use html_editor::operation::*;
+//!
+//! let nodes = parse(r#"<!DOCTYPE html><p class="intro">Hello<!-- x --></p>"#).unwrap();
+//! let bytes = to_bytes(&nodes).unwrap();
+//!
+//! assert_eq!(from_bytes(&bytes).unwrap().html(), nodes.html());
+//! ```
+//!
+//! The bytes start with a header, which is [`MAGIC`] followed by the
+//! [`VERSION`] of the format. Then come the nodes, as a list: its length
+//! followed by each node. A node is a byte for its kind followed by its
+//! content, where the lengths are 32-bit little-endian integers, and the
+//! strings are prefixed with their length in bytes. The children of an
+//! element are a list too, and elements can be nested at most
+//! [`MAX_DEPTH`] deep.
+
+use crate::{Doctype, Element, Node};
+
+/// The bytes the format starts with.
+pub const MAGIC: [u8; 4] = *b"HTED";
+
+/// The version of the format, which is written after [`MAGIC`]. Bytes
+/// written by another version can't be read.
+pub const VERSION: u8 = 1;
+
+/// How deep elements can be nested, so that reading untrusted bytes can't
+/// overflow the stack.
+pub const MAX_DEPTH: usize = 512;
+
+const ELEMENT: u8 = 0;
+const DIRTY_ELEMENT: u8 = 1;
+const TEXT: u8 = 2;
+const COMMENT: u8 = 3;
+const HTML_DOCTYPE: u8 = 4;
+const HTML_DOCTYPE_SPELLED: u8 = 5;
+const XML_DOCTYPE: u8 = 6;
+const RAW_HTML: u8 = 7;
+const XML_DOCTYPE_WITH_ENCODING: u8 = 8;
+const CDATA: u8 = 9;
+
+/// Why the nodes could not be written by [`to_bytes`], or the bytes read by
+/// [`from_bytes`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum BinaryError {
+    /// The bytes don't start with [`MAGIC`].
+    InvalidHeader,
+    /// The bytes were written by another version of the format.
+    UnsupportedVersion(u8),
+    /// The bytes end in the middle of a node.
+    UnexpectedEnd,
+    /// The byte is not the kind of any node.
+    InvalidNodeKind(u8),
+    /// A string is not valid UTF-8.
+    InvalidUtf8,
+    /// There are bytes left after the nodes.
+    TrailingBytes,
+    /// A string or a list is longer than a 32-bit length can hold.
+    TooLong,
+    /// The elements are nested deeper than [`MAX_DEPTH`].
+    TooDeep,
+}
+
+/// Write the nodes in the binary format.
+pub fn to_bytes(nodes: &[Node]) -> Result<Vec<u8>, BinaryError> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(VERSION);
+    write_nodes(nodes, 1, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Read the nodes written by [`to_bytes`].
+///
+/// ```
+/// use html_editor::binary::{from_bytes, to_bytes, BinaryError, VERSION};
+///
+/// let mut bytes = to_bytes(&[]).unwrap();
+/// assert!(from_bytes(&bytes).unwrap().is_empty());
+///
+/// bytes[4] = VERSION + 1;
+/// assert_eq!(from_bytes(&bytes).unwrap_err(), BinaryError::UnsupportedVersion(VERSION + 1));
+/// ```
+pub fn from_bytes(bytes: &[u8]) -> Result<Vec<Node>, BinaryError> {
+    let mut reader = Reader { bytes };
+    if reader.take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+        return Err(BinaryError::InvalidHeader);
+    }
+    match reader.byte()? {
+        VERSION => {}
+        version => return Err(BinaryError::UnsupportedVersion(version)),
+    }
+    let nodes = reader.nodes(1)?;
+    if !reader.bytes.is_empty() {
+        return Err(BinaryError::TrailingBytes);
+    }
+    Ok(nodes)
+}
+
+fn write_len(len: usize, bytes: &mut Vec<u8>) -> Result<(), BinaryError> {
+    let len = u32::try_from(len).map_err(|_| BinaryError::TooLong)?;
+    bytes.extend_from_slice(&len.to_le_bytes());
+    Ok(())
+}
+
+fn write_str(str: &str, bytes: &mut Vec<u8>) -> Result<(), BinaryError> {
+    write_len(str.len(), bytes)?;
+    bytes.extend_from_slice(str.as_bytes());
+    Ok(())
+}
+
+// The `depth` is how deep the nodes are, from 1 for the top-level ones.
+fn write_nodes(nodes: &[Node], depth: usize, bytes: &mut Vec<u8>) -> Result<(), BinaryError> {
+    write_len(nodes.len(), bytes)?;
+    for node in nodes {
+        write_node(node, depth, bytes)?;
+    }
+    Ok(())
+}
+
+fn write_node(node: &Node, depth: usize, bytes: &mut Vec<u8>) -> Result<(), BinaryError> {
+    match node {
+        Node::Element(el) => {
+            // An element nested deeper than the reader accepts would be
+            // written for nothing.
+            if depth > MAX_DEPTH {
+                return Err(BinaryError::TooDeep);
+            }
+            bytes.push(if el.dirty { DIRTY_ELEMENT } else { ELEMENT });
+            write_str(&el.name, bytes)?;
+            write_len(el.attrs.len(), bytes)?;
+            for (name, value) in &el.attrs {
+                write_str(name, bytes)?;
+                write_str(value, bytes)?;
+            }
+            write_nodes(&el.children, depth + 1, bytes)?;
+        }
+        Node::Text(text) => {
+            bytes.push(TEXT);
+            write_str(text, bytes)?;
+        }
+        Node::Comment(comment) => {
+            bytes.push(COMMENT);
+            write_str(comment, bytes)?;
+        }
+        Node::CData(text) => {
+            bytes.push(CDATA);
+            write_str(text, bytes)?;
+        }
+        Node::Doctype(Doctype::Html) => bytes.push(HTML_DOCTYPE),
+        Node::Doctype(Doctype::Spelled(source)) => {
+            bytes.push(HTML_DOCTYPE_SPELLED);
+            write_str(source, bytes)?;
+        }
+        Node::Doctype(Doctype::Xml {
+            version,
+            encoding,
+            single_quoted,
+        }) => {
+            bytes.push(match encoding {
+                Some(_) => XML_DOCTYPE_WITH_ENCODING,
+                None => XML_DOCTYPE,
+            });
+            write_str(version, bytes)?;
+            bytes.push(*single_quoted as u8);
+            if let Some(encoding) = encoding {
+                write_str(encoding, bytes)?;
+            }
+        }
+        Node::RawHTML(html) => {
+            bytes.push(RAW_HTML);
+            write_str(html, bytes)?;
+        }
+    }
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BinaryError> {
+        if self.bytes.len() < len {
+            return Err(BinaryError::UnexpectedEnd);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, BinaryError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> Result<usize, BinaryError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    }
+
+    fn string(&mut self) -> Result<String, BinaryError> {
+        let len = self.len()?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| BinaryError::InvalidUtf8)
+    }
+
+    fn nodes(&mut self, depth: usize) -> Result<Vec<Node>, BinaryError> {
+        let len = self.len()?;
+        // Each node takes a byte at least, so a wrong length can't make it
+        // allocate more than the bytes given.
+        let mut nodes = Vec::with_capacity(len.min(self.bytes.len()));
+        for _ in 0..len {
+            nodes.push(self.node(depth)?);
+        }
+        Ok(nodes)
+    }
+
+    fn node(&mut self, depth: usize) -> Result<Node, BinaryError> {
+        let node = match self.byte()? {
+            kind @ (ELEMENT | DIRTY_ELEMENT) => {
+                if depth > MAX_DEPTH {
+                    return Err(BinaryError::TooDeep);
+                }
+                let name = self.string()?;
+                let len = self.len()?;
+                let mut attrs = Vec::with_capacity(len.min(self.bytes.len()));
+                for _ in 0..len {
+                    attrs.push((self.string()?, self.string()?));
+                }
+                Node::Element(Element {
+                    name,
+                    attrs,
+                    children: self.nodes(depth + 1)?,
+                    dirty: kind == DIRTY_ELEMENT,
+                })
+            }
+            TEXT => Node::Text(self.string()?),
+            COMMENT => Node::Comment(self.string()?),
+            CDATA => Node::CData(self.string()?),
+            HTML_DOCTYPE => Node::Doctype(Doctype::Html),
+            HTML_DOCTYPE_SPELLED => Node::Doctype(Doctype::Spelled(self.string()?)),
+            kind @ (XML_DOCTYPE | XML_DOCTYPE_WITH_ENCODING) => Node::Doctype(Doctype::Xml {
+                version: self.string()?,
+                single_quoted: self.byte()? != 0,
+                encoding: match kind {
+                    XML_DOCTYPE_WITH_ENCODING => Some(self.string()?),
+                    _ => None,
+                },
+            }),
+            RAW_HTML => Node::RawHTML(self.string()?),
+            kind => return Err(BinaryError::InvalidNodeKind(kind)),
+        };
+        Ok(node)
+    }
+}
@@ -36,20 +36,62 @@
 mod data;
 mod parse;
 
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use data::BLOCK_TAGS;
+
+use operation::{Scope, Selector, Siblings};
+
 pub mod operation;
 
+#[cfg(feature = "binary")]
+pub mod binary;
+
 pub use parse::{parse, SourceLocation, HTMLParseError, InnerHTMLParseError};
 pub use parse::try_parse;
 
+pub use data::BOOLEAN_ATTRS;
+pub use parse::prettify;
+pub use parse::select_streaming;
+pub use parse::{parse_with_options, try_parse_with_options, ParseOptions};
+pub use parse::{parse_with_warnings, ParseResult, ParseWarning};
+
 /// Doctype of Html or Xml
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Doctype {
     Html,
-    Xml { version: String, encoding: String },
+    /// An html doctype written another way than `<!DOCTYPE html>`, like
+    /// `<!doctype html>` or a legacy doctype, as it is written. It is written
+    /// back as is with
+    /// [`SerializeOptions::preserve_doctype`](operation::SerializeOptions::preserve_doctype),
+    /// and as `<!DOCTYPE html>` otherwise.
+    Spelled(String),
+    /// The xml declaration, like `<?xml version="1.0" encoding="UTF-8"?>`.
+    Xml {
+        version: String,
+        /// The encoding, which can be left out of the declaration.
+        encoding: Option<String>,
+        /// Whether the values are quoted with `'` instead of `"`, as they
+        /// are written back the way they were parsed.
+        single_quoted: bool,
+    },
+}
+
+/// How [`Element::normalize_boolean_attributes`] and
+/// [`SerializeOptions::boolean_attributes`](operation::SerializeOptions::boolean_attributes)
+/// write the boolean attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BooleanAttributes {
+    /// With an empty value, which is written as the bare name, like
+    /// `checked`.
+    BareNames,
+    /// With their name as value, like `checked="checked"`.
+    Valued,
 }
 
 /// Node of DOM
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Node {
     Element(Element),
     /// A text node in the DOM. The contents of the `Text` has all entities expanded.
@@ -60,6 +102,12 @@ pub enum Node {
     /// `<style>` element using [Htmlifiable::html](operation::Htmlifiable::html).
     Text(String),
     Comment(String),
+    /// A CDATA section, like `<![CDATA[x < y]]>`, whose content is text
+    /// written as is. The parser only gives it in foreign content, that is
+    /// in `<svg>` and `<math>`, or with
+    /// [`ParseOptions::xml`](ParseOptions::xml), as in HTML it is a comment,
+    /// unless it contains `-->`.
+    CData(String),
     Doctype(Doctype),
     /// Raw HTML: similar to Text, but will not be escaped in any way when HTMLifying.
     /// This will never be generated by parsing
@@ -79,6 +127,21 @@ impl Node {
         matches!(self, Node::Element { .. })
     }
 
+    /// Check if it is a text node which is only ASCII whitespace, like the
+    /// indentation between elements. Empty text counts too, but other
+    /// whitespace, like a non-breaking space, is content.
+    ///
+    /// ```
+    /// use html_editor::Node;
+    ///
+    /// assert!(Node::Text("\n\t \r".to_string()).is_whitespace_text());
+    /// assert!(!Node::Text("\u{a0}".to_string()).is_whitespace_text());
+    /// assert!(!Node::Comment(" ".to_string()).is_whitespace_text());
+    /// ```
+    pub fn is_whitespace_text(&self) -> bool {
+        matches!(self, Node::Text(text) if text.bytes().all(|byte| byte.is_ascii_whitespace()))
+    }
+
     #[deprecated(note = "Please use `is_element` instead")]
     pub fn into_element(self) -> Element {
         match self {
@@ -129,6 +192,45 @@ impl Node {
         }
     }
 
+    /// Get the value of the attribute `name` if it is an element, see
+    /// [`Element::get_attr`].
+    ///
+    /// ```
+    /// use html_editor::Node;
+    ///
+    /// let a: Node = Node::new_element("a", vec![("href", "/")], vec![]);
+    /// assert_eq!(a.get_attr("href"), Some("/"));
+    /// assert_eq!(Node::Text("/".to_string()).get_attr("href"), None);
+    /// ```
+    pub fn get_attr(&self, name: &str) -> Option<&str> {
+        self.as_element()?.get_attr(name)
+    }
+
+    /// Get the tag name if it is an element.
+    ///
+    /// ```
+    /// use html_editor::Node;
+    ///
+    /// assert_eq!(Node::new_element("div", vec![], vec![]).name(), Some("div"));
+    /// assert_eq!(Node::Comment("div".to_string()).name(), None);
+    /// ```
+    pub fn name(&self) -> Option<&str> {
+        Some(&self.as_element()?.name)
+    }
+
+    /// Get the children if it is an element.
+    ///
+    /// ```
+    /// use html_editor::Node;
+    ///
+    /// let p: Node = Node::new_element("p", vec![], vec![Node::Text("Hi".to_string())]);
+    /// assert_eq!(p.children().map(|children| children.len()), Some(1));
+    /// assert!(Node::Text("Hi".to_string()).children().is_none());
+    /// ```
+    pub fn children(&self) -> Option<&[Node]> {
+        Some(&self.as_element()?.children)
+    }
+
     /// Create a new element node.
     ///
     /// ```
@@ -150,17 +252,23 @@ impl Node {
                 .map(|(k, v)| (k.to_string(), v.to_string()))
                 .collect(),
             children,
+            dirty: true,
         }
         .into_node()
     }
 }
 
 /// HTML Element
-#[derive(Debug, Clone)]
+///
+/// Two elements are equal when their content is, whether they are
+/// [dirty](Element::is_dirty) or not.
+#[derive(Clone)]
 pub struct Element {
     pub name: String,
     pub attrs: Vec<(String, String)>,
     pub children: Vec<Node>,
+    /// See [`Element::is_dirty`].
+    dirty: bool,
 }
 
 impl Element {
@@ -173,14 +281,635 @@ impl Element {
                 .map(|(k, v)| (k.to_string(), v.to_string()))
                 .collect(),
             children,
+            dirty: true,
         }
     }
+
+    /// Check whether the children of the element have been changed since it
+    /// was parsed, by APIs like [`Editable::insert_to`](operation::Editable::insert_to).
+    /// Elements which are not created by the parser start as dirty.
+    ///
+    /// It is only used by [`Format::Dirty`](operation::Format::Dirty), to
+    /// keep the layout of the parts of a document which were not edited.
+    ///
+    /// ```
+    /// use html_editor::{parse, Element};
+    /// use html_editor::operation::*;
+    ///
+    /// let mut nodes = parse("<ul><li>1</li></ul>").unwrap();
+    /// let ul = nodes[0].as_element_mut().unwrap();
+    /// assert!(!ul.is_dirty());
+    ///
+    /// ul.insert_to(&Selector::from("ul"), Element::new("li", vec![], vec![]).into_node());
+    /// assert!(ul.is_dirty());
+    ///
+    /// ul.mark_clean();
+    /// assert!(!ul.is_dirty());
+    /// ```
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Mark the element as not [dirty](Element::is_dirty), as if it was
+    /// parsed. Its descendants are left as they are.
+    pub fn mark_clean(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Get the first child node, whatever type it is.
+    ///
+    /// ```
+    /// use html_editor::{Element, Node};
+    ///
+    /// let p = Element::new("p", vec![], vec![
+    ///     Node::Text("Hello, ".to_string()),
+    ///     Node::new_element("b", vec![], vec![Node::Text("world".to_string())]),
+    /// ]);
+    /// assert!(matches!(p.first_child(), Some(Node::Text(_))));
+    /// assert!(matches!(p.last_child(), Some(Node::Element(_))));
+    /// ```
+    pub fn first_child(&self) -> Option<&Node> {
+        self.children.first()
+    }
+
+    /// Get the first child node as mutable, whatever type it is.
+    pub fn first_child_mut(&mut self) -> Option<&mut Node> {
+        self.children.first_mut()
+    }
+
+    /// Get the last child node, whatever type it is.
+    pub fn last_child(&self) -> Option<&Node> {
+        self.children.last()
+    }
+
+    /// Get the last child node as mutable, whatever type it is.
+    pub fn last_child_mut(&mut self) -> Option<&mut Node> {
+        self.children.last_mut()
+    }
+
+    /// Get the text content of the element, roughly as it is rendered.
+    ///
+    /// Runs of whitespace are collapsed into a single space, except in
+    /// `<pre>` and `<textarea>`, block elements like `<p>` are put on
+    /// their own lines, and `<br>` breaks the line. The contents of
+    /// `<script>`, `<style>` and `<template>` are left out, as well as
+    /// comments.
+    ///
+    /// ```
+    /// use html_editor::parse;
+    ///
+    /// let nodes = parse("<div><h1>Title</h1>  Lorem &amp;\n  <b>ipsum</b><br>dolor</div>").unwrap();
+    /// let div = nodes[0].as_element().unwrap();
+    /// assert_eq!(div.inner_text(), "Title\nLorem & ipsum\ndolor");
+    /// ```
+    pub fn inner_text(&self) -> String {
+        let mut text = String::new();
+        push_inner_text(&mut text, &self.children, self.is_preformatted());
+        text.trim_end_matches(|ch: char| ch.is_ascii_whitespace())
+            .to_string()
+    }
+
+    /// Get the [`inner_text`](Element::inner_text) as a text node.
+    pub fn to_text_node(&self) -> Node {
+        Node::Text(self.inner_text())
+    }
+
+    /// Replace the children with a single text node of the
+    /// [`inner_text`](Element::inner_text).
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let mut nodes = parse("<p>Hello, <b>world</b>!</p>").unwrap();
+    /// let p = nodes[0].as_element_mut().unwrap();
+    /// p.flatten_to_text();
+    /// assert_eq!(p.html(), "<p>Hello, world!</p>");
+    /// ```
+    pub fn flatten_to_text(&mut self) {
+        self.children = vec![self.to_text_node()];
+        self.dirty = true;
+    }
+
+    fn is_preformatted(&self) -> bool {
+        self.name.eq_ignore_ascii_case("pre") || self.name.eq_ignore_ascii_case("textarea")
+    }
+
+    /// Parse `html` and append the nodes to the children, keeping the
+    /// existing ones.
+    ///
+    /// The content of `<script>` and `<style>` is not html, so `html` is
+    /// appended to them as a text node instead.
+    ///
+    /// ```
+    /// use html_editor::Element;
+    /// use html_editor::operation::Htmlifiable;
+    ///
+    /// let mut ul = Element::new("ul", vec![], vec![]);
+    /// ul.append_html("<li>1</li>").unwrap();
+    /// ul.append_html("<li>2</li>").unwrap();
+    /// assert_eq!(ul.html(), "<ul><li>1</li><li>2</li></ul>");
+    ///
+    /// assert!(ul.append_html("<li>3").is_err());
+    /// ```
+    pub fn append_html(&mut self, html: &str) -> Result<(), HTMLParseError> {
+        if self.name.eq_ignore_ascii_case("script") || self.name.eq_ignore_ascii_case("style") {
+            self.children.push(Node::Text(html.to_string()));
+        } else {
+            self.children.append(&mut parse(html)?);
+        }
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Insert `node` right before the child at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// ```
+    /// use html_editor::{Element, Node};
+    /// use html_editor::operation::Htmlifiable;
+    ///
+    /// let mut ul = Element::new("ul", vec![], vec![
+    ///     Node::new_element("li", vec![], vec![Node::Text("2".to_string())]),
+    /// ]);
+    /// ul.insert_before_child_at(0, Node::new_element("li", vec![], vec![Node::Text("1".to_string())]));
+    /// assert_eq!(ul.html(), "<ul><li>1</li><li>2</li></ul>");
+    /// ```
+    pub fn insert_before_child_at(&mut self, index: usize, node: Node) {
+        assert!(index < self.children.len(), "child index out of bounds");
+        self.children.insert(index, node);
+        self.dirty = true;
+    }
+
+    /// Insert `node` right after the child at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn insert_after_child_at(&mut self, index: usize, node: Node) {
+        assert!(index < self.children.len(), "child index out of bounds");
+        self.children.insert(index + 1, node);
+        self.dirty = true;
+    }
+
+    /// Move all the children to the end of the children of `dest`, keeping
+    /// their order, which leaves the element empty.
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let mut nodes = parse("<ul><li>1</li></ul><ol><li>2</li><li>3</li></ol>").unwrap();
+    /// let (ul, ol) = nodes.split_at_mut(1);
+    /// let (ul, ol) = (ul[0].as_element_mut().unwrap(), ol[0].as_element_mut().unwrap());
+    ///
+    /// ol.move_children_to(ul);
+    /// assert_eq!(nodes.html(), "<ul><li>1</li><li>2</li><li>3</li></ul><ol></ol>");
+    /// ```
+    pub fn move_children_to(&mut self, dest: &mut Element) {
+        if self.children.is_empty() {
+            return;
+        }
+        dest.children.append(&mut self.children);
+        self.dirty = true;
+        dest.dirty = true;
+    }
+
+    /// Insert `node` right before the first child element that
+    /// matches the `selector`.
+    ///
+    /// Only the direct children are checked. Returns `false` and drops
+    /// `node` if none of them matches.
+    pub fn insert_before_child_matching(&mut self, selector: &Selector, node: Node) -> bool {
+        match self.position_of_child(selector) {
+            Some(index) => {
+                self.children.insert(index, node);
+                self.dirty = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Insert `node` right after the first child element that
+    /// matches the `selector`.
+    ///
+    /// Only the direct children are checked. Returns `false` and drops
+    /// `node` if none of them matches.
+    ///
+    /// ```
+    /// use html_editor::{parse, Node};
+    /// use html_editor::operation::*;
+    ///
+    /// let mut nodes = parse("<table><colgroup></colgroup><tr></tr></table>").unwrap();
+    /// let table = nodes[0].as_element_mut().unwrap();
+    ///
+    /// let caption = Node::new_element("caption", vec![], vec![]);
+    /// assert!(table.insert_after_child_matching(&Selector::from("colgroup"), caption));
+    /// assert_eq!(table.html(), "<table><colgroup></colgroup><caption></caption><tr></tr></table>");
+    /// ```
+    pub fn insert_after_child_matching(&mut self, selector: &Selector, node: Node) -> bool {
+        match self.position_of_child(selector) {
+            Some(index) => {
+                self.children.insert(index + 1, node);
+                self.dirty = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn position_of_child(&self, selector: &Selector) -> Option<usize> {
+        let scope = Scope::root(self);
+        let children = &self.children;
+        children
+            .iter()
+            .enumerate()
+            .position(|(index, child)| match child {
+                Node::Element(element) => {
+                    selector.matches_in(&scope.child(element, Siblings::Nodes(children, index)))
+                }
+                _ => false,
+            })
+    }
+
+    /// Get the attributes as a map, sorted by the attribute names.
+    ///
+    /// If an attribute name appears more than once, the first one in
+    /// `attrs` wins, which is also the one checked by a
+    /// [`Selector`](operation::Selector).
+    ///
+    /// ```
+    /// use html_editor::Element;
+    ///
+    /// let element = Element::new("a", vec![("id", "home"), ("href", "/"), ("id", "index")], vec![]);
+    /// let attrs = element.attributes_map();
+    ///
+    /// assert_eq!(attrs.keys().collect::<Vec<_>>(), vec!["href", "id"]);
+    /// assert_eq!(attrs["id"], "home");
+    /// ```
+    pub fn attributes_map(&self) -> BTreeMap<String, String> {
+        let mut map = BTreeMap::new();
+        for (key, value) in &self.attrs {
+            map.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        map
+    }
+
+    /// Get the attributes as a map, keeping the order of `attrs`.
+    ///
+    /// Duplicate attribute names are handled the same way as in
+    /// [`attributes_map`](Element::attributes_map): the first one wins.
+    ///
+    /// Requires the `indexmap` feature.
+    #[cfg(feature = "indexmap")]
+    pub fn attributes_index_map(&self) -> indexmap::IndexMap<String, String> {
+        let mut map = indexmap::IndexMap::new();
+        for (key, value) in &self.attrs {
+            map.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        map
+    }
+
+    /// Get the value of the attribute `name`.
+    ///
+    /// If the attribute appears more than once, the first one wins, like
+    /// for [`attributes_map`](Element::attributes_map).
+    ///
+    /// ```
+    /// use html_editor::Element;
+    ///
+    /// let input = Element::new("input", vec![("type", "text"), ("type", "number")], vec![]);
+    /// assert_eq!(input.get_attr("type"), Some("text"));
+    /// assert_eq!(input.get_attr("value"), None);
+    /// ```
+    pub fn get_attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Check if the element has the attribute `name`, which is matched ASCII
+    /// case-insensitively, as attribute names are in HTML.
+    ///
+    /// ```
+    /// use html_editor::Element;
+    ///
+    /// let button = Element::new("button", vec![("DISABLED", "")], vec![]);
+    /// assert!(button.has_attr("disabled"));
+    /// assert!(!button.has_attr("hidden"));
+    /// ```
+    pub fn has_attr(&self, name: &str) -> bool {
+        self.attrs
+            .iter()
+            .any(|(key, _)| key.eq_ignore_ascii_case(name))
+    }
+
+    /// Check if the attribute `name`, matched like for
+    /// [`has_attr`](Element::has_attr), has exactly the value `value`.
+    ///
+    /// If the attribute appears more than once, only the first one is
+    /// checked, like for [`get_attr`](Element::get_attr). See
+    /// [`has_attr_value_ignore_case`](Element::has_attr_value_ignore_case)
+    /// to compare the value ASCII case-insensitively.
+    ///
+    /// ```
+    /// use html_editor::Element;
+    ///
+    /// let a = Element::new("a", vec![("target", "_blank")], vec![]);
+    /// assert!(a.has_attr_value("Target", "_blank"));
+    /// assert!(!a.has_attr_value("target", "_Blank"));
+    /// ```
+    pub fn has_attr_value(&self, name: &str, value: &str) -> bool {
+        self.attr_ignore_case(name) == Some(value)
+    }
+
+    /// Same as [`has_attr_value`](Element::has_attr_value), but the value is
+    /// compared ASCII case-insensitively.
+    ///
+    /// ```
+    /// use html_editor::Element;
+    ///
+    /// let input = Element::new("input", vec![("type", "Checkbox")], vec![]);
+    /// assert!(input.has_attr_value_ignore_case("type", "checkbox"));
+    /// ```
+    pub fn has_attr_value_ignore_case(&self, name: &str, value: &str) -> bool {
+        self.attr_ignore_case(name)
+            .is_some_and(|attr| attr.eq_ignore_ascii_case(value))
+    }
+
+    fn attr_ignore_case(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Get the `id` attribute, see [`get_attr`](Element::get_attr).
+    ///
+    /// ```
+    /// use html_editor::Element;
+    ///
+    /// let div = Element::new("div", vec![("id", "app")], vec![]);
+    /// assert_eq!(div.id(), Some("app"));
+    /// ```
+    pub fn id(&self) -> Option<&str> {
+        self.get_attr("id")
+    }
+
+    /// Set the `id` attribute, replacing the value of the first one, or
+    /// adding it after the others if there is none.
+    ///
+    /// As an id can't be empty, setting it to an empty string removes all
+    /// the `id` attributes instead.
+    ///
+    /// ```
+    /// use html_editor::Element;
+    /// use html_editor::operation::Htmlifiable;
+    ///
+    /// let mut div = Element::new("div", vec![("class", "box")], vec![]);
+    /// div.set_id("main");
+    /// assert_eq!(div.html(), r#"<div class="box" id="main"></div>"#);
+    ///
+    /// div.set_id("");
+    /// assert_eq!(div.html(), r#"<div class="box"></div>"#);
+    /// ```
+    pub fn set_id(&mut self, id: impl Into<String>) {
+        let id = id.into();
+        if id.is_empty() {
+            self.attrs.retain(|(key, _)| key != "id");
+            return;
+        }
+        match self.attrs.iter_mut().find(|(key, _)| key == "id") {
+            Some((_, value)) => *value = id,
+            None => self.attrs.push(("id".to_string(), id)),
+        }
+    }
+
+    /// Add the attribute `name` with the value `default_value`, after the
+    /// others, unless the element already has it, and return its value.
+    ///
+    /// The name is matched like for [`has_attr`](Element::has_attr), and
+    /// an existing value is kept even if it is empty.
+    ///
+    /// ```
+    /// use html_editor::Element;
+    /// use html_editor::operation::Htmlifiable;
+    ///
+    /// let mut img = Element::new("img", vec![("src", "a.png"), ("loading", "eager")], vec![]);
+    /// assert_eq!(img.ensure_attr("loading", "lazy"), "eager");
+    /// assert_eq!(img.ensure_attr("alt", ""), "");
+    /// assert_eq!(img.html(), r#"<img src="a.png" loading="eager" alt="">"#);
+    /// ```
+    pub fn ensure_attr(&mut self, name: &str, default_value: impl Into<String>) -> &str {
+        let index = match self
+            .attrs
+            .iter()
+            .position(|(key, _)| key.eq_ignore_ascii_case(name))
+        {
+            Some(index) => index,
+            None => {
+                self.attrs.push((name.to_string(), default_value.into()));
+                self.attrs.len() - 1
+            }
+        };
+        &self.attrs[index].1
+    }
+
+    /// Iterate over the class names in the `class` attribute, which are
+    /// separated by ASCII whitespace. Only the first `class` attribute is
+    /// read, see [`get_attr`](Element::get_attr).
+    ///
+    /// ```
+    /// use html_editor::Element;
+    ///
+    /// let div = Element::new("div", vec![("class", " card\tbig ")], vec![]);
+    /// assert_eq!(div.classes().collect::<Vec<_>>(), vec!["card", "big"]);
+    /// ```
+    pub fn classes(&self) -> impl Iterator<Item = &str> {
+        self.get_attr("class")
+            .unwrap_or_default()
+            .split_ascii_whitespace()
+    }
+
+    /// Check if `name` is one of the [`classes`](Element::classes) of the
+    /// element. Class names are case-sensitive.
+    ///
+    /// ```
+    /// use html_editor::Element;
+    ///
+    /// let button = Element::new("button", vec![("class", "btn\nprimary")], vec![]);
+    /// assert!(button.has_class("primary"));
+    /// assert!(!button.has_class("Primary"));
+    /// ```
+    pub fn has_class(&self, name: &str) -> bool {
+        self.classes().any(|class| class == name)
+    }
+
+    /// Remove the class names which appear more than once in the `class`
+    /// attribute, keeping the first one. The names are then separated by
+    /// single spaces.
+    ///
+    /// ```
+    /// use html_editor::Element;
+    /// use html_editor::operation::Htmlifiable;
+    ///
+    /// let mut a = Element::new("a", vec![("class", "btn  btn active\tbtn")], vec![]);
+    /// a.dedup_classes();
+    /// assert_eq!(a.html(), r#"<a class="btn active"></a>"#);
+    /// ```
+    pub fn dedup_classes(&mut self) {
+        self.rewrite_classes(|classes| {
+            let mut seen = HashSet::new();
+            classes.retain(|class| seen.insert(*class));
+        });
+    }
+
+    /// Sort the class names in the `class` attribute, so that elements with
+    /// the same classes are written the same way. The names are then
+    /// separated by single spaces.
+    ///
+    /// Duplicates are kept, see [`dedup_classes`](Element::dedup_classes).
+    ///
+    /// ```
+    /// use html_editor::Element;
+    /// use html_editor::operation::Htmlifiable;
+    ///
+    /// let mut a = Element::new("a", vec![("class", "nav active link")], vec![]);
+    /// a.sort_classes();
+    /// assert_eq!(a.html(), r#"<a class="active link nav"></a>"#);
+    /// ```
+    pub fn sort_classes(&mut self) {
+        self.rewrite_classes(|classes| classes.sort_unstable());
+    }
+
+    fn rewrite_classes(&mut self, f: impl FnOnce(&mut Vec<&str>)) {
+        if let Some((_, value)) = self.attrs.iter_mut().find(|(key, _)| key == "class") {
+            let classes = {
+                let mut classes: Vec<&str> = value.split_ascii_whitespace().collect();
+                f(&mut classes);
+                classes.join(" ")
+            };
+            *value = classes;
+        }
+    }
+
+    /// Rewrite the values of the boolean attributes, see [`BOOLEAN_ATTRS`],
+    /// of the element and all its descendants in the given style.
+    ///
+    /// A boolean attribute is true whatever its value, so all of them get
+    /// the same one, even `disabled="false"`. The names are matched ASCII
+    /// case-insensitively, and the lowercase name is used as value by
+    /// [`BooleanAttributes::Valued`].
+    ///
+    /// ```
+    /// use html_editor::{parse, BooleanAttributes};
+    /// use html_editor::operation::*;
+    ///
+    /// let mut nodes = parse(r#"<form><input DISABLED="false"></form>"#).unwrap();
+    /// let form = nodes[0].as_element_mut().unwrap();
+    ///
+    /// form.normalize_boolean_attributes(BooleanAttributes::BareNames);
+    /// assert_eq!(form.html(), "<form><input DISABLED></form>");
+    ///
+    /// form.normalize_boolean_attributes(BooleanAttributes::Valued);
+    /// assert_eq!(form.html(), r#"<form><input DISABLED="disabled"></form>"#);
+    /// ```
+    pub fn normalize_boolean_attributes(&mut self, style: BooleanAttributes) {
+        for (name, value) in &mut self.attrs {
+            if BOOLEAN_ATTRS
+                .iter()
+                .any(|attr| attr.eq_ignore_ascii_case(name))
+            {
+                *value = match style {
+                    BooleanAttributes::BareNames => String::new(),
+                    BooleanAttributes::Valued => name.to_ascii_lowercase(),
+                };
+            }
+        }
+        for child in &mut self.children {
+            if let Node::Element(element) = child {
+                element.normalize_boolean_attributes(style);
+            }
+        }
+    }
+
+    /// Move the first attribute named `name` to the front, keeping the
+    /// order of the others.
+    ///
+    /// Returns `false` if there is no such attribute.
+    ///
+    /// ```
+    /// use html_editor::Element;
+    /// use html_editor::operation::Htmlifiable;
+    ///
+    /// let mut a = Element::new("a", vec![("href", "/"), ("id", "home"), ("class", "nav")], vec![]);
+    /// assert!(a.move_attr_to_front("class"));
+    /// assert_eq!(a.html(), r#"<a class="nav" href="/" id="home"></a>"#);
+    /// ```
+    pub fn move_attr_to_front(&mut self, name: &str) -> bool {
+        match self.attrs.iter().position(|(key, _)| key == name) {
+            Some(index) => {
+                self.attrs[..=index].rotate_right(1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sort the attributes by the key `f` returns for each name and value.
+    ///
+    /// The sort is stable, so the attributes with the same key keep their
+    /// order.
+    ///
+    /// ```
+    /// use html_editor::Element;
+    /// use html_editor::operation::Htmlifiable;
+    ///
+    /// let mut img = Element::new("img", vec![("src", "a.png"), ("alt", ""), ("id", "logo")], vec![]);
+    /// img.sort_attrs_by(|name, _| name != "id");
+    /// assert_eq!(img.html(), r#"<img id="logo" src="a.png" alt="">"#);
+    /// ```
+    pub fn sort_attrs_by<K: Ord>(&mut self, mut f: impl FnMut(&str, &str) -> K) {
+        self.attrs.sort_by_key(|(name, value)| f(name, value));
+    }
 }
 
 impl Element {
     pub fn into_node(self) -> Node {
         Node::Element(self)
     }
+
+    /// Clone the element without its children.
+    pub(crate) fn shallow_clone(&self) -> Element {
+        Element {
+            name: self.name.clone(),
+            attrs: self.attrs.clone(),
+            children: Vec::new(),
+            dirty: self.dirty,
+        }
+    }
+}
+
+// `dirty` is left out, as it is not part of the content.
+impl fmt::Debug for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Element")
+            .field("name", &self.name)
+            .field("attrs", &self.attrs)
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+impl PartialEq for Element {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.attrs == other.attrs && self.children == other.children
+    }
 }
 
 impl From<Element> for Node {
@@ -188,3 +917,58 @@ impl From<Element> for Node {
         Node::Element(element)
     }
 }
+
+fn push_inner_text(text: &mut String, nodes: &[Node], preformatted: bool) {
+    for node in nodes {
+        match node {
+            Node::Text(content) | Node::CData(content) if preformatted => text.push_str(content),
+            Node::Text(content) | Node::CData(content) => {
+                for ch in content.chars() {
+                    if !ch.is_ascii_whitespace() {
+                        text.push(ch);
+                    } else if !text.is_empty() && !text.ends_with([' ', '\n']) {
+                        text.push(' ');
+                    }
+                }
+            }
+            Node::Element(element) => {
+                let name = element.name.to_ascii_lowercase();
+                match name.as_str() {
+                    "script" | "style" | "template" => {}
+                    "br" => {
+                        trim_line_end(text);
+                        text.push('\n');
+                    }
+                    _ if BLOCK_TAGS.contains(&name.as_str()) => {
+                        break_line(text);
+                        push_inner_text(
+                            text,
+                            &element.children,
+                            preformatted || element.is_preformatted(),
+                        );
+                        break_line(text);
+                    }
+                    _ => push_inner_text(
+                        text,
+                        &element.children,
+                        preformatted || element.is_preformatted(),
+                    ),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn trim_line_end(text: &mut String) {
+    let len = text.trim_end_matches(' ').len();
+    text.truncate(len);
+}
+
+/// Start a new line, unless already at the start of one.
+fn break_line(text: &mut String) {
+    trim_line_end(text);
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
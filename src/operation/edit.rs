@@ -1,9 +1,43 @@
-use super::Selector;
+use super::{copy_elements, Queryable, Scope, Selector, Siblings};
+use crate::parse::is_void_tag;
 use crate::{Element, Node};
 
+/// Options to control which text [`Editable::map_text_with`] maps.
+///
+/// ```
+/// use html_editor::operation::MapTextOptions;
+///
+/// let options = MapTextOptions {
+///     into_raw_text: true,
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MapTextOptions {
+    /// Whether to map the content of `<script>` and `<style>` too.
+    ///
+    /// It is code rather than text to read, so it is skipped by default.
+    pub into_raw_text: bool,
+}
+
 /// Insert and remove elements by [`Selector`], and trim the DOM.
+///
+/// The operations return `self`, except for [`detach`](Editable::detach),
+/// so that they can be chained:
+///
+/// ```
+/// use html_editor::{parse, Node};
+/// use html_editor::operation::*;
+///
+/// let mut doc = parse("<head></head><body><div class=\"ad\"></div><b>Old</b></body>").unwrap();
+/// doc.remove_by(&Selector::from(".ad"))
+///     .insert_to(&Selector::from("head"), Node::new_element("meta", vec![("charset", "utf-8")], vec![]))
+///     .replace_with(&Selector::from("b"), Node::Text("New".into()));
+///
+/// assert_eq!(doc.html(), r#"<head><meta charset="utf-8"></head><body>New</body>"#);
+/// ```
 pub trait Editable {
-    /// Remove all empty text nodes from `self`.
+    /// Remove all empty text nodes from `self`, that is the ones which are
+    /// only whitespace, see [`Node::is_whitespace_text`], and the comments.
     ///
     /// ```
     /// use html_editor::parse;
@@ -44,6 +78,8 @@ pub trait Editable {
 
     /// Remove all elements that matches the `selector`.
     ///
+    /// The elements are matched before any of them is removed.
+    ///
     /// ```
     /// use html_editor::parse;
     /// use html_editor::operation::*;
@@ -65,6 +101,434 @@ pub trait Editable {
     /// </div>"#)
     /// ```
     fn remove_by(&mut self, selector: &Selector) -> &mut Self;
+
+    /// Replace all elements that matches the `selector` with `target`.
+    ///
+    /// The elements are matched before any of them is replaced, and
+    /// `target` is not searched for matches, so it is never replaced itself.
+    ///
+    /// ```
+    /// use html_editor::{parse, Node};
+    /// use html_editor::operation::*;
+    ///
+    /// let html = r#"<p>Hello <font color="red">World</font></p>"#;
+    ///
+    /// let selector = Selector::from("font");
+    /// let target = Node::new_element("em", vec![], vec![Node::Text("World".into())]);
+    /// let html = parse(html).unwrap().replace_with(&selector, target).html();
+    /// assert_eq!(html, "<p>Hello <em>World</em></p>");
+    /// ```
+    fn replace_with(&mut self, selector: &Selector, target: Node) -> &mut Self;
+
+    /// Remove all elements that matches the `selector`, and return them
+    /// with their children in document order.
+    ///
+    /// Unlike [`remove_by`](Editable::remove_by), the removed elements can
+    /// then be inserted somewhere else.
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let mut dom = parse("<body><nav>Menu</nav><main>Content</main></body>").unwrap();
+    ///
+    /// let nav = dom.detach(&Selector::from("nav"));
+    /// for node in nav {
+    ///     dom.insert_to(&Selector::from("body"), node);
+    /// }
+    /// assert_eq!(dom.html(), "<body><main>Content</main><nav>Menu</nav></body>");
+    /// ```
+    fn detach(&mut self, selector: &Selector) -> Vec<Node>;
+
+    /// Replace the node at `path` with `node`, and return the node which
+    /// was there, or `None` if the path doesn't lead to a node, as after
+    /// the DOM is restructured. See
+    /// [`Queryable::query_all_paths`](Queryable::query_all_paths)
+    /// for the paths. An [`Element`] can't replace itself, so its empty
+    /// path leads to no node.
+    ///
+    /// The parent of the node is marked as [dirty](crate::Element::is_dirty).
+    ///
+    /// ```
+    /// use html_editor::{parse, Node};
+    /// use html_editor::operation::*;
+    ///
+    /// let mut nodes = parse("<p>Hello, <i>world</i>!</p><p><i>Bye</i></p>").unwrap();
+    ///
+    /// for path in nodes.query_all_paths(&Selector::from("i")) {
+    ///     let i = nodes.element_at(&path).unwrap();
+    ///     let b = Node::new_element("b", vec![], i.children.clone());
+    ///     nodes.replace_at(&path, b);
+    /// }
+    /// assert_eq!(nodes.html(), "<p>Hello, <b>world</b>!</p><p><b>Bye</b></p>");
+    ///
+    /// assert!(nodes.replace_at(&[1, 1], Node::Text("?".into())).is_none());
+    /// ```
+    fn replace_at(&mut self, path: &[usize], node: Node) -> Option<Node>;
+
+    /// Move the children of void elements, like `<img>`, which can't have
+    /// any, to right after the element, keeping their order.
+    ///
+    /// Void elements nested in void elements are repaired first, so their
+    /// children end up after the outermost one. The children of `self` are
+    /// never moved, as it has no siblings.
+    ///
+    /// ```
+    /// use html_editor::{Element, Node};
+    /// use html_editor::operation::*;
+    ///
+    /// let mut figure = Element::new(
+    ///     "figure",
+    ///     vec![],
+    ///     vec![Node::new_element(
+    ///         "img",
+    ///         vec![("src", "cat.png")],
+    ///         vec![Node::new_element("figcaption", vec![], vec![Node::Text("Cat".into())])],
+    ///     )],
+    /// );
+    ///
+    /// let html = figure.repair_void_elements().html();
+    /// assert_eq!(html, r#"<figure><img src="cat.png"><figcaption>Cat</figcaption></figure>"#);
+    /// ```
+    fn repair_void_elements(&mut self) -> &mut Self;
+
+    /// Replace each element for which `f` returns `true` with its children.
+    ///
+    /// The children of an element are unwrapped before `f` is called on it,
+    /// so it sees the element as it ends up, and the children moved up are
+    /// never checked again: there is nothing left to unwrap afterwards. As
+    /// for [`remove_by`](Editable::remove_by), `self` itself is never
+    /// unwrapped.
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let html = r#"<p><span>Plain <span>text</span></span> and <span class="red">red</span></p>"#;
+    /// let html = parse(html)
+    ///     .unwrap()
+    ///     .unwrap_elements_where(|el| el.name == "span" && el.attrs.is_empty())
+    ///     .html();
+    /// assert_eq!(html, r#"<p>Plain text and <span class="red">red</span></p>"#);
+    /// ```
+    fn unwrap_elements_where(&mut self, f: impl FnMut(&Element) -> bool) -> &mut Self;
+
+    /// Call `f` for each node in `self`, in document order, with the
+    /// children of its parent and its index among them, so that it can
+    /// look at and edit its siblings. The walk stops when `f` returns
+    /// `false`. The content of `<template>` elements is visited as well.
+    ///
+    /// After `f` returns, the walk goes into the children of the node
+    /// which is then at `index`, and continues with the node at
+    /// `index + 1`. So nodes after the current one can be freely inserted
+    /// or removed, and are visited as they are when the walk gets there.
+    /// Inserting or removing nodes at or before `index` shifts the current
+    /// node: it is visited twice if a node is inserted before it, and the
+    /// node following it is skipped if it is removed. To remove the current
+    /// node, replace it instead, for example with an empty text node.
+    ///
+    /// When the number of children of an element changes, it is marked as
+    /// [dirty](crate::Element::is_dirty).
+    ///
+    /// ```
+    /// use html_editor::{parse, Node};
+    /// use html_editor::operation::*;
+    ///
+    /// let mut nodes = parse("<p>a<br><br><br>b<br></p>").unwrap();
+    ///
+    /// // Collapse consecutive `<br>`s
+    /// nodes.walk(|siblings, index| {
+    ///     let is_br = |node: Option<&Node>| node.and_then(Node::name) == Some("br");
+    ///     while is_br(siblings.get(index)) && is_br(siblings.get(index + 1)) {
+    ///         siblings.remove(index + 1);
+    ///     }
+    ///     true
+    /// });
+    /// assert_eq!(nodes.html(), "<p>a<br>b<br></p>");
+    /// ```
+    fn walk(&mut self, f: impl FnMut(&mut Vec<Node>, usize) -> bool) -> &mut Self;
+
+    /// Replace the content of each text node in `self`, including the
+    /// [`Node::CData`] sections, with what `f` returns for it. Comments and
+    /// attribute values are left as they are.
+    ///
+    /// The content of `<script>` and `<style>` is skipped, see
+    /// [`MapTextOptions`].
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let mut nodes = parse(r#"<p title="hi">Hello, <b>World</b>!</p><script>x = "a"</script>"#).unwrap();
+    /// nodes.map_text(|text| text.to_uppercase());
+    ///
+    /// assert_eq!(nodes.html(), r#"<p title="hi">HELLO, <b>WORLD</b>!</p><script>x = "a"</script>"#);
+    /// ```
+    fn map_text(&mut self, f: impl FnMut(&str) -> String) -> &mut Self {
+        self.map_text_with(&MapTextOptions::default(), f)
+    }
+
+    /// Same as [`map_text`](Editable::map_text), but with the given
+    /// [`MapTextOptions`].
+    fn map_text_with(
+        &mut self,
+        options: &MapTextOptions,
+        f: impl FnMut(&str) -> String,
+    ) -> &mut Self;
+
+    /// Replace each occurrence of `from` with `to` in the values of the
+    /// attributes named one of `attr_names`, on every element in `self`. The
+    /// names are matched ASCII case-insensitively, and nothing is replaced
+    /// if `from` is empty.
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let html = r#"<a href="https://old.cdn.com/a.zip"><img src="https://old.cdn.com/a.png">old.cdn.com</a>"#;
+    /// let mut nodes = parse(html).unwrap();
+    /// nodes.replace_attr_substring(&["src", "href"], "//old.cdn.com/", "//new.cdn.com/");
+    ///
+    /// assert_eq!(
+    ///     nodes.html(),
+    ///     r#"<a href="https://new.cdn.com/a.zip"><img src="https://new.cdn.com/a.png">old.cdn.com</a>"#
+    /// );
+    /// ```
+    fn replace_attr_substring(&mut self, attr_names: &[&str], from: &str, to: &str) -> &mut Self;
+
+    /// Same as [`map_text`](Editable::map_text), but takes `self` and
+    /// returns it, for building a new tree.
+    ///
+    /// ```
+    /// use html_editor::Node;
+    /// use html_editor::operation::*;
+    ///
+    /// let text = Node::Text("secret".to_string());
+    /// let redacted = text.with_mapped_text(|text| "*".repeat(text.len()));
+    ///
+    /// assert_eq!(redacted.html(), "******");
+    /// ```
+    fn with_mapped_text(mut self, f: impl FnMut(&str) -> String) -> Self
+    where
+        Self: Sized,
+    {
+        self.map_text(f);
+        self
+    }
+}
+
+// The elements are matched against a copy of themselves without their
+// children, as their ancestors cannot be borrowed while they are edited.
+
+fn nodes_insert_to(nodes: &mut [Node], parent: Option<&Scope>, selector: &Selector, target: &Node) {
+    let copies = copy_elements(nodes);
+    let elements = nodes.iter_mut().filter_map(Node::as_element_mut);
+    for (index, el) in elements.enumerate() {
+        let scope = Scope::new(&copies[index], Siblings::Elements(&copies, index), parent);
+        element_insert_to(el, &scope, selector, target);
+    }
+}
+
+fn element_insert_to(el: &mut Element, scope: &Scope, selector: &Selector, target: &Node) {
+    nodes_insert_to(&mut el.children, Some(scope), selector, target);
+    if selector.matches_in(scope) {
+        el.children.push(target.clone());
+        el.dirty = true;
+    }
+}
+
+// Match all the nodes first, as removing or replacing one changes the
+// siblings of the others.
+fn match_nodes(nodes: &[Node], parent: Option<&Scope>, selector: &Selector) -> Vec<bool> {
+    nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| match node {
+            Node::Element(el) => {
+                selector.matches_in(&Scope::new(el, Siblings::Nodes(nodes, index), parent))
+            }
+            _ => false,
+        })
+        .collect()
+}
+
+// The descendants are edited first, so that they are matched against the
+// siblings as they were before the edit too.
+fn nodes_remove_by(nodes: &mut Vec<Node>, parent: Option<&Scope>, selector: &Selector) {
+    let removed = match_nodes(nodes, parent, selector);
+
+    let copies = copy_elements(nodes);
+    let mut index = 0;
+    for (node, &removed) in nodes.iter_mut().zip(&removed) {
+        if let Node::Element(el) = node {
+            if !removed {
+                let scope = Scope::new(&copies[index], Siblings::Elements(&copies, index), parent);
+                let len = el.children.len();
+                nodes_remove_by(&mut el.children, Some(&scope), selector);
+                el.dirty |= el.children.len() != len;
+            }
+            index += 1;
+        }
+    }
+
+    let mut removed = removed.into_iter();
+    nodes.retain(|_| !removed.next().unwrap_or(false));
+}
+
+// Returns whether any of `nodes` was replaced.
+fn nodes_replace_with(
+    nodes: &mut [Node],
+    parent: Option<&Scope>,
+    selector: &Selector,
+    target: &Node,
+) -> bool {
+    let replaced = match_nodes(nodes, parent, selector);
+
+    let copies = copy_elements(nodes);
+    let mut index = 0;
+    for (node, &replaced) in nodes.iter_mut().zip(&replaced) {
+        if let Node::Element(el) = node {
+            if !replaced {
+                let scope = Scope::new(&copies[index], Siblings::Elements(&copies, index), parent);
+                el.dirty |= nodes_replace_with(&mut el.children, Some(&scope), selector, target);
+            }
+            index += 1;
+        }
+    }
+
+    for (node, &replaced) in nodes.iter_mut().zip(&replaced) {
+        if replaced {
+            *node = target.clone();
+        }
+    }
+    replaced.contains(&true)
+}
+
+fn nodes_detach(
+    nodes: &mut Vec<Node>,
+    parent: Option<&Scope>,
+    selector: &Selector,
+    detached: &mut Vec<Node>,
+) {
+    let copies = copy_elements(nodes);
+    let mut index = 0;
+    for mut node in std::mem::take(nodes) {
+        if let Node::Element(el) = &mut node {
+            let scope = Scope::new(&copies[index], Siblings::Elements(&copies, index), parent);
+            index += 1;
+            if selector.matches_in(&scope) {
+                detached.push(node);
+                continue;
+            }
+            let len = el.children.len();
+            nodes_detach(&mut el.children, Some(&scope), selector, detached);
+            el.dirty |= el.children.len() != len;
+        }
+        nodes.push(node);
+    }
+}
+
+// Returns whether any node was moved to `nodes`.
+fn nodes_repair_void_elements(nodes: &mut Vec<Node>) -> bool {
+    let mut repaired = false;
+    for mut node in std::mem::take(nodes) {
+        let mut moved = Vec::new();
+        if let Node::Element(el) = &mut node {
+            el.dirty |= nodes_repair_void_elements(&mut el.children);
+            if is_void_tag(&el.name) && !el.children.is_empty() {
+                moved = std::mem::take(&mut el.children);
+                el.dirty = true;
+                repaired = true;
+            }
+        }
+        nodes.push(node);
+        nodes.append(&mut moved);
+    }
+    repaired
+}
+
+// Returns `false` once `f` has asked to stop.
+fn nodes_walk(nodes: &mut Vec<Node>, f: &mut impl FnMut(&mut Vec<Node>, usize) -> bool) -> bool {
+    let mut index = 0;
+    while index < nodes.len() {
+        if !f(nodes, index) {
+            return false;
+        }
+        if let Some(Node::Element(el)) = nodes.get_mut(index) {
+            let len = el.children.len();
+            let walking = nodes_walk(&mut el.children, f);
+            el.dirty |= el.children.len() != len;
+            if !walking {
+                return false;
+            }
+        }
+        index += 1;
+    }
+    true
+}
+
+// Returns whether any of `nodes` was unwrapped.
+fn nodes_unwrap_elements_where(
+    nodes: &mut Vec<Node>,
+    f: &mut impl FnMut(&Element) -> bool,
+) -> bool {
+    let mut unwrapped = false;
+    for mut node in std::mem::take(nodes) {
+        if let Node::Element(el) = &mut node {
+            el.dirty |= nodes_unwrap_elements_where(&mut el.children, f);
+            if f(el) {
+                nodes.append(&mut el.children);
+                unwrapped = true;
+                continue;
+            }
+        }
+        nodes.push(node);
+    }
+    unwrapped
+}
+
+fn nodes_map_text(
+    nodes: &mut [Node],
+    options: &MapTextOptions,
+    f: &mut impl FnMut(&str) -> String,
+) {
+    for node in nodes {
+        match node {
+            Node::Text(text) | Node::CData(text) => *text = f(text),
+            Node::Element(el) if options.into_raw_text || !is_raw_text_element(el) => {
+                nodes_map_text(&mut el.children, options, f)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn nodes_replace_attr_substring(nodes: &mut [Node], attr_names: &[&str], from: &str, to: &str) {
+    for el in nodes.iter_mut().filter_map(Node::as_element_mut) {
+        element_replace_attr_substring(el, attr_names, from, to);
+    }
+}
+
+fn element_replace_attr_substring(el: &mut Element, attr_names: &[&str], from: &str, to: &str) {
+    for (name, value) in &mut el.attrs {
+        let named = attr_names
+            .iter()
+            .any(|attr| attr.eq_ignore_ascii_case(name));
+        if named && value.contains(from) {
+            *value = value.replace(from, to);
+        }
+    }
+    nodes_replace_attr_substring(&mut el.children, attr_names, from, to);
+}
+
+fn replace_child(el: &mut Element, index: usize, node: Node) -> Option<Node> {
+    let old = std::mem::replace(el.children.get_mut(index)?, node);
+    el.dirty = true;
+    Some(old)
+}
+
+fn is_raw_text_element(el: &Element) -> bool {
+    el.name.eq_ignore_ascii_case("script") || el.name.eq_ignore_ascii_case("style")
 }
 
 impl Editable for Vec<Node> {
@@ -72,71 +536,254 @@ impl Editable for Vec<Node> {
         self.retain(|node| match node {
             Node::Doctype(..) => true,
             Node::Comment(..) => false,
-            Node::Text(text) => !text.trim().is_empty(),
+            Node::Text(..) => !node.is_whitespace_text(),
             Node::Element { .. } => true,
             Node::RawHTML { .. } => true,
+            Node::CData(..) => true,
         });
         for node in self.iter_mut() {
             if let Node::Element(el) = node {
+                let len = el.children.len();
                 el.children.trim();
+                el.dirty |= el.children.len() != len;
             }
         }
         self
     }
 
     fn insert_to(&mut self, selector: &Selector, target: Node) -> &mut Self {
-        for node in self.iter_mut() {
-            if let Node::Element(el) = node {
-                el.children.insert_to(selector, target.clone());
-                if selector.matches(&Element {
-                    name: el.name.clone(),
-                    attrs: el.attrs.clone(),
-                    children: vec![],
-                }) {
-                    el.children.push(target.clone());
-                }
-            }
-        }
+        nodes_insert_to(self, None, selector, &target);
         self
     }
 
     fn remove_by(&mut self, selector: &Selector) -> &mut Self {
-        self.retain(|node| {
-            if let Node::Element(el) = node {
-                let element = Element {
-                    name: el.name.clone(),
-                    attrs: el.attrs.clone(),
-                    children: vec![],
-                };
-                return !selector.matches(&element);
-            }
-            true
-        });
-        for node in self.iter_mut() {
-            if let Node::Element(el) = node {
-                el.remove_by(selector);
+        nodes_remove_by(self, None, selector);
+        self
+    }
+
+    fn replace_with(&mut self, selector: &Selector, target: Node) -> &mut Self {
+        nodes_replace_with(self, None, selector, &target);
+        self
+    }
+
+    fn detach(&mut self, selector: &Selector) -> Vec<Node> {
+        let mut detached = Vec::new();
+        nodes_detach(self, None, selector, &mut detached);
+        detached
+    }
+
+    fn replace_at(&mut self, path: &[usize], node: Node) -> Option<Node> {
+        match path.split_last()? {
+            (index, []) => Some(std::mem::replace(self.get_mut(*index)?, node)),
+            (index, parent) => {
+                let parent = self.element_at_mut(parent)?;
+                replace_child(parent, *index, node)
             }
         }
+    }
+
+    fn repair_void_elements(&mut self) -> &mut Self {
+        nodes_repair_void_elements(self);
+        self
+    }
+
+    fn walk(&mut self, mut f: impl FnMut(&mut Vec<Node>, usize) -> bool) -> &mut Self {
+        nodes_walk(self, &mut f);
+        self
+    }
+
+    fn unwrap_elements_where(&mut self, mut f: impl FnMut(&Element) -> bool) -> &mut Self {
+        nodes_unwrap_elements_where(self, &mut f);
+        self
+    }
+
+    fn replace_attr_substring(&mut self, attr_names: &[&str], from: &str, to: &str) -> &mut Self {
+        if !from.is_empty() {
+            nodes_replace_attr_substring(self, attr_names, from, to);
+        }
+        self
+    }
+
+    fn map_text_with(
+        &mut self,
+        options: &MapTextOptions,
+        mut f: impl FnMut(&str) -> String,
+    ) -> &mut Self {
+        nodes_map_text(self, options, &mut f);
         self
     }
 }
 
 impl Editable for Element {
     fn trim(&mut self) -> &mut Self {
+        let len = self.children.len();
         self.children.trim();
+        self.dirty |= self.children.len() != len;
+        self
+    }
+
+    fn insert_to(&mut self, selector: &Selector, target: Node) -> &mut Self {
+        let copy = self.shallow_clone();
+        element_insert_to(self, &Scope::root(&copy), selector, &target);
+        self
+    }
+
+    fn remove_by(&mut self, selector: &Selector) -> &mut Self {
+        let copy = self.shallow_clone();
+        let len = self.children.len();
+        nodes_remove_by(&mut self.children, Some(&Scope::root(&copy)), selector);
+        self.dirty |= self.children.len() != len;
+        self
+    }
+
+    fn replace_with(&mut self, selector: &Selector, target: Node) -> &mut Self {
+        let copy = self.shallow_clone();
+        self.dirty |= nodes_replace_with(
+            &mut self.children,
+            Some(&Scope::root(&copy)),
+            selector,
+            &target,
+        );
+        self
+    }
+
+    fn detach(&mut self, selector: &Selector) -> Vec<Node> {
+        let copy = self.shallow_clone();
+        let len = self.children.len();
+        let mut detached = Vec::new();
+        nodes_detach(
+            &mut self.children,
+            Some(&Scope::root(&copy)),
+            selector,
+            &mut detached,
+        );
+        self.dirty |= self.children.len() != len;
+        detached
+    }
+
+    fn replace_at(&mut self, path: &[usize], node: Node) -> Option<Node> {
+        let (index, parent) = path.split_last()?;
+        let parent = match parent {
+            [] => self,
+            parent => self.element_at_mut(parent)?,
+        };
+        replace_child(parent, *index, node)
+    }
+
+    fn repair_void_elements(&mut self) -> &mut Self {
+        self.dirty |= nodes_repair_void_elements(&mut self.children);
+        self
+    }
+
+    fn walk(&mut self, mut f: impl FnMut(&mut Vec<Node>, usize) -> bool) -> &mut Self {
+        let len = self.children.len();
+        nodes_walk(&mut self.children, &mut f);
+        self.dirty |= self.children.len() != len;
+        self
+    }
+
+    fn unwrap_elements_where(&mut self, mut f: impl FnMut(&Element) -> bool) -> &mut Self {
+        self.dirty |= nodes_unwrap_elements_where(&mut self.children, &mut f);
+        self
+    }
+
+    fn replace_attr_substring(&mut self, attr_names: &[&str], from: &str, to: &str) -> &mut Self {
+        if !from.is_empty() {
+            element_replace_attr_substring(self, attr_names, from, to);
+        }
+        self
+    }
+
+    fn map_text_with(
+        &mut self,
+        options: &MapTextOptions,
+        mut f: impl FnMut(&str) -> String,
+    ) -> &mut Self {
+        if options.into_raw_text || !is_raw_text_element(self) {
+            nodes_map_text(&mut self.children, options, &mut f);
+        }
+        self
+    }
+}
+
+impl Editable for Node {
+    fn trim(&mut self) -> &mut Self {
+        if let Some(element) = self.as_element_mut() {
+            element.trim();
+        }
         self
     }
 
     fn insert_to(&mut self, selector: &Selector, target: Node) -> &mut Self {
-        self.children.insert_to(selector, target.clone());
-        if selector.matches(self) {
-            self.children.push(target);
+        if let Some(element) = self.as_element_mut() {
+            element.insert_to(selector, target);
         }
         self
     }
 
     fn remove_by(&mut self, selector: &Selector) -> &mut Self {
-        self.children.remove_by(selector);
+        if let Some(element) = self.as_element_mut() {
+            element.remove_by(selector);
+        }
+        self
+    }
+
+    fn replace_with(&mut self, selector: &Selector, target: Node) -> &mut Self {
+        if let Some(element) = self.as_element_mut() {
+            element.replace_with(selector, target);
+        }
+        self
+    }
+
+    fn detach(&mut self, selector: &Selector) -> Vec<Node> {
+        match self.as_element_mut() {
+            Some(element) => element.detach(selector),
+            None => Vec::new(),
+        }
+    }
+
+    fn replace_at(&mut self, path: &[usize], node: Node) -> Option<Node> {
+        if path.is_empty() {
+            Some(std::mem::replace(self, node))
+        } else {
+            self.as_element_mut()?.replace_at(path, node)
+        }
+    }
+
+    fn repair_void_elements(&mut self) -> &mut Self {
+        if let Some(element) = self.as_element_mut() {
+            element.repair_void_elements();
+        }
+        self
+    }
+
+    fn walk(&mut self, f: impl FnMut(&mut Vec<Node>, usize) -> bool) -> &mut Self {
+        if let Some(element) = self.as_element_mut() {
+            element.walk(f);
+        }
+        self
+    }
+
+    fn unwrap_elements_where(&mut self, f: impl FnMut(&Element) -> bool) -> &mut Self {
+        if let Some(element) = self.as_element_mut() {
+            element.unwrap_elements_where(f);
+        }
+        self
+    }
+
+    fn replace_attr_substring(&mut self, attr_names: &[&str], from: &str, to: &str) -> &mut Self {
+        if let Some(element) = self.as_element_mut() {
+            element.replace_attr_substring(attr_names, from, to);
+        }
+        self
+    }
+
+    fn map_text_with(
+        &mut self,
+        options: &MapTextOptions,
+        mut f: impl FnMut(&str) -> String,
+    ) -> &mut Self {
+        nodes_map_text(std::slice::from_mut(self), options, &mut f);
         self
     }
 }
@@ -1,4 +1,211 @@
-use crate::{data::VOID_TAGS, Doctype, Element, Node};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{
+    data::{BOOLEAN_ATTRS, VOID_TAGS},
+    BooleanAttributes, Doctype, Element, Node,
+};
+
+/// Options to control how the DOM is turned into html.
+///
+/// ```
+/// use html_editor::Node;
+/// use html_editor::operation::*;
+///
+/// let node = Node::new_element("br", vec![], vec![]);
+/// assert_eq!(node.html_with(&SerializeOptions::default()), "<br>");
+/// assert_eq!(node.html_with(&SerializeOptions::xml()), "<br/>");
+/// ```
+#[derive(Clone)]
+pub struct SerializeOptions {
+    /// The elements written without an end tag by
+    /// [`EmptyElements::Html`]. Defaults to the void elements of HTML.
+    pub void_tags: Vec<String>,
+    /// How to write the elements which have no content.
+    pub empty_elements: EmptyElements,
+    /// How to lay out the nodes.
+    pub format: Format,
+    /// Whether to write the html doctypes as they were parsed, like
+    /// `<!doctype html>`, instead of `<!DOCTYPE html>`.
+    pub preserve_doctype: bool,
+    /// Whether to write `'` as `&apos;` in text and attribute values, as
+    /// some XML consumers expect. HTML output leaves it literal, which is
+    /// the default.
+    ///
+    /// ```
+    /// use html_editor::Node;
+    /// use html_editor::operation::*;
+    ///
+    /// let node = Node::new_element("q", vec![("title", "It's")], vec![Node::Text("I'm".into())]);
+    /// assert_eq!(node.html(), r#"<q title="It's">I'm</q>"#);
+    /// assert_eq!(
+    ///     node.html_with(&SerializeOptions::xml()),
+    ///     r#"<q title="It&apos;s">I&apos;m</q>"#
+    /// );
+    /// ```
+    pub escape_apostrophes: bool,
+    /// How to write the boolean attributes, see
+    /// [`BOOLEAN_ATTRS`](crate::BOOLEAN_ATTRS), which have an empty value.
+    /// The other attributes are always written with their value, like
+    /// `alt=""`. Defaults to [`BooleanAttributes::BareNames`], while
+    /// [`SerializeOptions::xml`] uses [`BooleanAttributes::Valued`], as XML
+    /// has no bare attribute names.
+    ///
+    /// ```
+    /// use html_editor::Node;
+    /// use html_editor::operation::*;
+    ///
+    /// let node = Node::new_element("input", vec![("disabled", ""), ("value", "")], vec![]);
+    /// assert_eq!(node.html(), r#"<input disabled value="">"#);
+    /// assert_eq!(
+    ///     node.html_with(&SerializeOptions::xml()),
+    ///     r#"<input disabled="disabled" value=""/>"#
+    /// );
+    /// ```
+    pub boolean_attributes: BooleanAttributes,
+    /// Functions writing the whole elements with the given tag names in
+    /// place of the default serialization. The tag names are matched ASCII
+    /// case-insensitively, so the keys should be lowercase.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use html_editor::{parse, Element};
+    /// use html_editor::operation::*;
+    ///
+    /// let nodes = parse(r#"<p>Sales</p><chart data="1,3"></chart>"#).unwrap();
+    ///
+    /// let mut options = SerializeOptions::default();
+    /// options.custom.insert(
+    ///     "chart".to_string(),
+    ///     Arc::new(|chart: &Element| format!("<svg><!-- {} --></svg>", chart.attrs[0].1)),
+    /// );
+    /// assert_eq!(nodes.html_with(&options), "<p>Sales</p><svg><!-- 1,3 --></svg>");
+    /// ```
+    pub custom: HashMap<String, CustomSerializer>,
+}
+
+/// A function writing an element with its children, see
+/// [`SerializeOptions::custom`]. It is shared by the clones of the
+/// options, which can be sent to other threads.
+pub type CustomSerializer = Arc<dyn Fn(&Element) -> String + Send + Sync>;
+
+impl fmt::Debug for SerializeOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SerializeOptions")
+            .field("void_tags", &self.void_tags)
+            .field("empty_elements", &self.empty_elements)
+            .field("format", &self.format)
+            .field("preserve_doctype", &self.preserve_doctype)
+            .field("escape_apostrophes", &self.escape_apostrophes)
+            .field("boolean_attributes", &self.boolean_attributes)
+            .field("custom", &self.custom.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl SerializeOptions {
+    /// Options to write the DOM as XML.
+    pub fn xml() -> Self {
+        SerializeOptions {
+            empty_elements: EmptyElements::Xml,
+            escape_apostrophes: true,
+            boolean_attributes: BooleanAttributes::Valued,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        SerializeOptions {
+            void_tags: VOID_TAGS.iter().map(|tag| tag.to_string()).collect(),
+            empty_elements: EmptyElements::default(),
+            format: Format::default(),
+            preserve_doctype: false,
+            escape_apostrophes: false,
+            boolean_attributes: BooleanAttributes::BareNames,
+            custom: HashMap::new(),
+        }
+    }
+}
+
+/// How to write the elements which have no content, see
+/// [`SerializeOptions::empty_elements`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptyElements {
+    /// Elements in [`SerializeOptions::void_tags`] are always written
+    /// as `<br>`, dropping their children if there are any. Other
+    /// elements always have an end tag.
+    #[default]
+    Html,
+    /// Elements without children are written as `<br/>`, and the others
+    /// have an end tag, like in XML.
+    Xml,
+    /// Every element has an end tag, like `<br></br>`.
+    Paired,
+}
+
+/// How to lay out the nodes, see [`SerializeOptions::format`].
+///
+/// Only the whitespace between the nodes is changed. The content of
+/// `<pre>`, `<textarea>`, `<script>` and `<style>` is always written as is.
+///
+/// ```
+/// use html_editor::{parse, Node};
+/// use html_editor::operation::*;
+///
+/// let html = "<ul>\n  <li>1</li>\n</ul>\n<p>Hi</p>";
+/// let mut nodes = parse(html).unwrap();
+/// nodes.insert_to(
+///     &Selector::from("p"),
+///     Node::new_element("b", vec![], vec![Node::Text("!".into())]),
+/// );
+///
+/// let pretty = SerializeOptions {
+///     format: Format::Pretty("    ".into()),
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     nodes.html_with(&pretty),
+///     "<ul>\n    <li>1</li>\n</ul>\n<p>\n    Hi\n    <b>!</b>\n</p>"
+/// );
+///
+/// // Only the `<p>` was edited, so the `<ul>` keeps its layout
+/// let dirty = SerializeOptions {
+///     format: Format::Dirty("    ".into()),
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     nodes.html_with(&dirty),
+///     "<ul>\n  <li>1</li>\n</ul>\n<p>\n    Hi\n    <b>!</b>\n</p>"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Format {
+    /// Write all the nodes as they are.
+    #[default]
+    AsIs,
+    /// Put each child on its own line, indented with the given string
+    /// once per level, unless the only child is a text. Text which is
+    /// only whitespace is dropped, and the whitespace around the other
+    /// text is trimmed.
+    Pretty(String),
+    /// Lay out the [dirty](crate::Element::is_dirty) elements like
+    /// [`Format::Pretty`], and write the others as they are. A dirty
+    /// element is indented from the whitespace before it, so the parts
+    /// of a parsed document which were not edited keep their layout.
+    Dirty(String),
+}
+
+impl Format {
+    fn indent(&self) -> &str {
+        match self {
+            Format::AsIs => "",
+            Format::Pretty(indent) | Format::Dirty(indent) => indent,
+        }
+    }
+}
 
 /// Stringify into html.
 pub trait Htmlifiable {
@@ -24,71 +231,63 @@ pub trait Htmlifiable {
     /// let element: Element = node.into_element();
     /// assert_eq!(element.html(), r#"<script src="index.js" defer></script>"#);
     /// ```
-    fn html(&self) -> String;
+    fn html(&self) -> String {
+        self.html_with(&SerializeOptions::default())
+    }
+
+    /// Convert the object to html string with the given
+    /// [`SerializeOptions`].
+    ///
+    /// ```
+    /// use html_editor::Node;
+    /// use html_editor::operation::*;
+    ///
+    /// let node = Node::new_element("path", vec![("d", "M 0 0")], vec![]);
+    ///
+    /// let options = SerializeOptions {
+    ///     empty_elements: EmptyElements::Xml,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(node.html_with(&options), r#"<path d="M 0 0"/>"#);
+    /// ```
+    fn html_with(&self, options: &SerializeOptions) -> String;
 }
 
 impl Htmlifiable for Element {
-    fn html(&self) -> String {
-        let children_html = match self.name.as_str() {
-            "style" | "script" => {
-                // <style> and <script> tags should not have their contents escaped
-                let mut html = String::new();
-                for node in &self.children {
-                    if let Node::Text(text) = node {
-                        html.push_str(text.as_str());
-                    } else {
-                        html.push_str(node.html().as_str());
-                    }
-                }
-                html
-            }
-            _ => self.children.html(),
+    fn html_with(&self, options: &SerializeOptions) -> String {
+        let indentation = match options.format {
+            Format::AsIs => None,
+            Format::Pretty(_) => Some(""),
+            Format::Dirty(_) => self.dirty.then_some(""),
         };
-
-        if self.attrs.is_empty() {
-            return if VOID_TAGS.contains(&self.name.as_str()) {
-                format!("<{}>", self.name)
-            } else {
-                format!("<{}>{}</{}>", self.name, children_html, self.name)
-            };
-        }
-        let attrs = self
-            .attrs
-            .iter()
-            .map(|(k, v)| {
-                if v.is_empty() {
-                    k.to_string()
-                } else {
-                    format!(r#"{}="{}""#, k, html_escape::encode_double_quoted_attribute(&v).into_owned())
-                }
-            })
-            .collect::<Vec<_>>()
-            .join(" ");
-
-        if VOID_TAGS.contains(&self.name.as_str()) {
-            format!("<{} {}>", self.name, attrs,)
-        } else {
-            format!(
-                "<{} {}>{}</{}>",
-                self.name,
-                attrs,
-                children_html,
-                self.name
-            )
-        }
+        let mut html = String::new();
+        write_element(self, options, 0, indentation, false, &mut html);
+        html
     }
 }
 
 impl Htmlifiable for Node {
-    fn html(&self) -> String {
+    fn html_with(&self, options: &SerializeOptions) -> String {
         match self {
-            Node::Element(element) => element.html(),
-            Node::Text(text) => html_escape::encode_text(text).into_owned(),
+            Node::Element(element) => element.html_with(options),
+            Node::Text(text) => encode_text(text, options),
             Node::Comment(comment) => format!("<!--{}-->", comment),
+            Node::CData(text) => format!("<![CDATA[{}]]>", text),
             Node::Doctype(doctype) => match &doctype {
-                Doctype::Html => "<!DOCTYPE html>".to_string(),
-                Doctype::Xml { version, encoding } => {
-                    format!(r#"<?xml version="{}" encoding="{}"?>"#, version, encoding)
+                Doctype::Spelled(source) if options.preserve_doctype => source.clone(),
+                Doctype::Html | Doctype::Spelled(_) => "<!DOCTYPE html>".to_string(),
+                Doctype::Xml {
+                    version,
+                    encoding,
+                    single_quoted,
+                } => {
+                    let quote = if *single_quoted { '\'' } else { '"' };
+                    let mut xml = format!("<?xml version={0}{1}{0}", quote, version);
+                    if let Some(encoding) = encoding {
+                        xml.push_str(&format!(" encoding={0}{1}{0}", quote, encoding));
+                    }
+                    xml.push_str("?>");
+                    xml
                 }
             },
             Node::RawHTML(html) => html.to_owned(),
@@ -97,11 +296,225 @@ impl Htmlifiable for Node {
 }
 
 impl Htmlifiable for Vec<Node> {
-    fn html(&self) -> String {
+    fn html_with(&self, options: &SerializeOptions) -> String {
         let mut html = String::new();
-        for node in self {
-            html.push_str(node.html().as_str());
+        match options.format {
+            Format::Pretty(_) => {
+                for (i, node) in skip_whitespace(self).enumerate() {
+                    if i > 0 {
+                        html.push('\n');
+                    }
+                    write_laid_out_node(node, options, 0, "", &mut html);
+                }
+            }
+            _ => write_nodes(self, options, 0, false, false, &mut html),
         }
         html
     }
 }
+
+// Writes `el`, laying out its children if `indentation`, the one of the
+// line it starts on, is given. Nothing under a `verbatim` element is laid out.
+fn write_element(
+    el: &Element,
+    options: &SerializeOptions,
+    depth: usize,
+    indentation: Option<&str>,
+    verbatim: bool,
+    html: &mut String,
+) {
+    if let Some(custom) = custom_serializer(el, options) {
+        html.push_str(&custom(el));
+        return;
+    }
+
+    html.push('<');
+    html.push_str(&el.name);
+    for (k, v) in &el.attrs {
+        html.push(' ');
+        // An empty value means something for most attributes, like an empty
+        // `alt`, so only the boolean ones are written as a bare name.
+        if v.is_empty()
+            && BOOLEAN_ATTRS
+                .iter()
+                .any(|attr| attr.eq_ignore_ascii_case(k))
+        {
+            match options.boolean_attributes {
+                BooleanAttributes::BareNames => html.push_str(k),
+                BooleanAttributes::Valued => {
+                    html.push_str(&format!(r#"{}="{}""#, k, k.to_ascii_lowercase()))
+                }
+            }
+        } else {
+            html.push_str(&format!(r#"{}="{}""#, k, encode_attribute(v, options)));
+        }
+    }
+
+    match options.empty_elements {
+        EmptyElements::Html
+            if options
+                .void_tags
+                .iter()
+                .any(|tag| tag.eq_ignore_ascii_case(&el.name)) =>
+        {
+            html.push('>');
+            return;
+        }
+        EmptyElements::Xml if el.children.is_empty() => {
+            html.push_str("/>");
+            return;
+        }
+        _ => html.push('>'),
+    }
+
+    let name = el.name.to_ascii_lowercase();
+    // <style> and <script> tags should not have their contents escaped
+    let raw = matches!(name.as_str(), "style" | "script");
+    let preformatted = raw || matches!(name.as_str(), "pre" | "textarea");
+    match indentation {
+        Some(indentation) if !preformatted => {
+            write_laid_out_children(&el.children, options, depth, indentation, html)
+        }
+        _ => write_nodes(
+            &el.children,
+            options,
+            depth + 1,
+            verbatim || preformatted,
+            raw,
+            html,
+        ),
+    }
+
+    html.push_str(&format!("</{}>", el.name));
+}
+
+fn custom_serializer<'a>(
+    el: &Element,
+    options: &'a SerializeOptions,
+) -> Option<&'a CustomSerializer> {
+    if options.custom.is_empty() {
+        return None;
+    }
+    options
+        .custom
+        .get(&el.name)
+        .or_else(|| options.custom.get(&el.name.to_ascii_lowercase()))
+}
+
+// Writes `nodes` as they are, except for the dirty elements with `Format::Dirty`.
+fn write_nodes(
+    nodes: &[Node],
+    options: &SerializeOptions,
+    depth: usize,
+    verbatim: bool,
+    raw: bool,
+    html: &mut String,
+) {
+    for (i, node) in nodes.iter().enumerate() {
+        match node {
+            Node::Element(el) => {
+                let indentation = match options.format {
+                    Format::Dirty(ref indent) if el.dirty && !verbatim => Some(
+                        indentation_before(&nodes[..i])
+                            .map_or_else(|| indent.repeat(depth), str::to_string),
+                    ),
+                    _ => None,
+                };
+                write_element(el, options, depth, indentation.as_deref(), verbatim, html);
+            }
+            Node::Text(text) if raw => html.push_str(text),
+            _ => html.push_str(&node.html_with(options)),
+        }
+    }
+}
+
+fn write_laid_out_children(
+    nodes: &[Node],
+    options: &SerializeOptions,
+    depth: usize,
+    indentation: &str,
+    html: &mut String,
+) {
+    let children: Vec<&Node> = skip_whitespace(nodes).collect();
+    match children.as_slice() {
+        [] => {}
+        [Node::Text(text)] => html.push_str(&encode_text(trim_whitespace(text), options)),
+        _ => {
+            let child_indentation = format!("{}{}", indentation, options.format.indent());
+            for node in children {
+                html.push('\n');
+                html.push_str(&child_indentation);
+                write_laid_out_node(node, options, depth + 1, &child_indentation, html);
+            }
+            html.push('\n');
+            html.push_str(indentation);
+        }
+    }
+}
+
+fn write_laid_out_node(
+    node: &Node,
+    options: &SerializeOptions,
+    depth: usize,
+    indentation: &str,
+    html: &mut String,
+) {
+    match node {
+        Node::Element(el) => write_element(el, options, depth, Some(indentation), false, html),
+        Node::Text(text) => html.push_str(&encode_text(trim_whitespace(text), options)),
+        _ => html.push_str(&node.html_with(options)),
+    }
+}
+
+// The indentation of the line a node starts on, if the text before it ends
+// with a line break and some whitespace.
+fn indentation_before(nodes: &[Node]) -> Option<&str> {
+    match nodes.last() {
+        Some(Node::Text(text)) => {
+            let (_, line) = text.rsplit_once('\n')?;
+            line.chars()
+                .all(|ch| ch.is_ascii_whitespace())
+                .then_some(line)
+        }
+        _ => None,
+    }
+}
+
+fn skip_whitespace(nodes: &[Node]) -> impl Iterator<Item = &Node> {
+    nodes.iter().filter(|node| !node.is_whitespace_text())
+}
+
+fn trim_whitespace(text: &str) -> &str {
+    text.trim_matches(|ch: char| ch.is_ascii_whitespace())
+}
+
+// Most text and attribute values have nothing to escape, so check for the
+// characters `html_escape` would replace before handing them over to it.
+
+fn encode_text(text: &str, options: &SerializeOptions) -> String {
+    let text = if text.contains(['&', '<', '>']) {
+        html_escape::encode_text(text).into_owned()
+    } else {
+        text.to_string()
+    };
+    encode_apostrophes(text, options)
+}
+
+// `<` and `>` are escaped in attribute values too, even though HTML allows
+// them there, so that the output is valid XML as well.
+fn encode_attribute(value: &str, options: &SerializeOptions) -> String {
+    let value = if value.contains(['&', '<', '>', '"']) {
+        html_escape::encode_double_quoted_attribute(value).into_owned()
+    } else {
+        value.to_string()
+    };
+    encode_apostrophes(value, options)
+}
+
+fn encode_apostrophes(text: String, options: &SerializeOptions) -> String {
+    if options.escape_apostrophes && text.contains('\'') {
+        text.replace('\'', "&apos;")
+    } else {
+        text
+    }
+}
@@ -0,0 +1,292 @@
+use crate::{data::BLOCK_TAGS, Doctype, Element, Node};
+
+/// Inspect the content of the DOM.
+pub trait Inspectable {
+    /// Hash the meaningful content of `self`, so that documents which only
+    /// differ in their formatting get the same digest.
+    ///
+    /// Before hashing, the tree is normalized as follows:
+    ///
+    /// - Tag and attribute names are lowercased, and the attributes are
+    ///   sorted by name, then by value.
+    /// - Comments are dropped, and the text nodes around them are joined.
+    /// - In text, each run of ASCII whitespace becomes a single space. It is
+    ///   trimmed next to block elements, like `<p>`, and at their start and
+    ///   end, so text which is only whitespace between them is dropped, but
+    ///   it is kept next to inline elements, like `<b>`, where it shows.
+    ///   The text in `<pre>`, `<textarea>`, `<script>` and `<style>` is kept
+    ///   as is.
+    /// - Text and attribute values are hashed as parsed, with their entities
+    ///   decoded, so `&amp;` and `&#38;` are the same. The content of
+    ///   [`Node::CData`](crate::Node::CData) is text as well.
+    /// - Doctypes are hashed by their kind, as in
+    ///   [`Doctype`](crate::Doctype), ignoring how they were spelled.
+    /// - [`Node::RawHTML`](crate::Node::RawHTML) is hashed as is, and
+    ///   [dirty](crate::Element::is_dirty) is ignored.
+    ///
+    /// The digest is a 64-bit FNV-1a hash, which is the same across runs
+    /// and platforms, but it is not cryptographic.
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let a = parse(r#"<ul class="nav" id="menu"><li>Home</li></ul>"#).unwrap();
+    /// let b = parse("<UL id='menu' class='nav'>\n  <!-- links -->\n  <li> Home </li>\n</UL>").unwrap();
+    /// let c = parse(r#"<ul class="nav" id="menu"><li>About</li></ul>"#).unwrap();
+    ///
+    /// assert_eq!(a.content_digest(), b.content_digest());
+    /// assert_ne!(a.content_digest(), c.content_digest());
+    /// ```
+    fn content_digest(&self) -> u64;
+
+    /// Count the nodes in `self` by kind, along with the attributes and how
+    /// deep the nodes are nested, in a single traversal.
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let nodes = parse(r#"<!DOCTYPE html><ul class="nav"><li>Home<!-- x --></li></ul>"#).unwrap();
+    ///
+    /// assert_eq!(
+    ///     nodes.stats(),
+    ///     TreeStats {
+    ///         nodes: 5,
+    ///         elements: 2,
+    ///         texts: 1,
+    ///         comments: 1,
+    ///         attributes: 1,
+    ///         max_depth: 3,
+    ///     }
+    /// );
+    /// ```
+    fn stats(&self) -> TreeStats;
+}
+
+/// The counts returned by [`Inspectable::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TreeStats {
+    /// The number of nodes of any kind.
+    pub nodes: usize,
+    /// The number of [`Node::Element`].
+    pub elements: usize,
+    /// The number of [`Node::Text`] and [`Node::CData`].
+    pub texts: usize,
+    /// The number of [`Node::Comment`].
+    pub comments: usize,
+    /// The number of attributes of all the elements.
+    pub attributes: usize,
+    /// The depth of the most nested node, where the nodes at the top are at
+    /// depth 1, or 0 if there is no node.
+    pub max_depth: usize,
+}
+
+impl TreeStats {
+    fn count(&mut self, nodes: &[Node], depth: usize) {
+        for node in nodes {
+            self.nodes += 1;
+            self.max_depth = self.max_depth.max(depth);
+            match node {
+                Node::Element(el) => {
+                    self.elements += 1;
+                    self.attributes += el.attrs.len();
+                    self.count(&el.children, depth + 1);
+                }
+                Node::Text(_) | Node::CData(_) => self.texts += 1,
+                Node::Comment(_) => self.comments += 1,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Inspectable for Vec<Node> {
+    fn content_digest(&self) -> u64 {
+        let mut digest = Digest::new();
+        digest.nodes(self, false, true);
+        digest.0
+    }
+
+    fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats::default();
+        stats.count(self, 1);
+        stats
+    }
+}
+
+impl Inspectable for Element {
+    fn content_digest(&self) -> u64 {
+        let mut digest = Digest::new();
+        digest.element(self, false);
+        digest.0
+    }
+
+    fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats {
+            nodes: 1,
+            elements: 1,
+            attributes: self.attrs.len(),
+            max_depth: 1,
+            ..Default::default()
+        };
+        stats.count(&self.children, 2);
+        stats
+    }
+}
+
+impl Inspectable for Node {
+    fn content_digest(&self) -> u64 {
+        let mut digest = Digest::new();
+        digest.nodes(std::slice::from_ref(self), false, true);
+        digest.0
+    }
+
+    fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats::default();
+        stats.count(std::slice::from_ref(self), 1);
+        stats
+    }
+}
+
+struct Digest(u64);
+
+// Each node is hashed as a byte for its kind followed by its content, and
+// strings are prefixed with their length, so that different trees can't be
+// written the same.
+impl Digest {
+    fn new() -> Self {
+        Digest(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: impl IntoIterator<Item = u8>) {
+        for byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write((len as u64).to_le_bytes());
+    }
+
+    fn write_str(&mut self, str: &str) {
+        self.write_len(str.len());
+        self.write(str.bytes());
+    }
+
+    fn write_name(&mut self, name: &str) {
+        self.write_len(name.len());
+        self.write(name.bytes().map(|byte| byte.to_ascii_lowercase()));
+    }
+
+    // `verbatim` is whether the nodes are in an element whose text is kept
+    // as is, and `block` whether they are in a block or at the top level.
+    fn nodes(&mut self, nodes: &[Node], verbatim: bool, block: bool) {
+        let mut text = String::new();
+        // Whether the text starts the block or follows a block element
+        let mut after_block = block;
+        for node in nodes {
+            match node {
+                Node::Text(content) | Node::CData(content) => text.push_str(content),
+                Node::Comment(_) => {}
+                _ => {
+                    let is_block = match node {
+                        Node::Element(el) => is_block(&el.name),
+                        Node::RawHTML(_) => false,
+                        _ => true,
+                    };
+                    self.text(&text, verbatim, after_block, is_block);
+                    text.clear();
+                    after_block = is_block;
+                    match node {
+                        Node::Element(el) => self.element(el, verbatim),
+                        Node::Doctype(Doctype::Html | Doctype::Spelled(_)) => self.write(*b"h"),
+                        Node::Doctype(Doctype::Xml {
+                            version, encoding, ..
+                        }) => {
+                            self.write(*b"x");
+                            self.write_str(version);
+                            if let Some(encoding) = encoding {
+                                self.write(*b"e");
+                                self.write_str(encoding);
+                            }
+                        }
+                        Node::RawHTML(html) => {
+                            self.write(*b"r");
+                            self.write_str(html);
+                        }
+                        Node::Text(_) | Node::CData(_) | Node::Comment(_) => unreachable!(),
+                    }
+                }
+            }
+        }
+        self.text(&text, verbatim, after_block, block);
+    }
+
+    fn text(&mut self, text: &str, verbatim: bool, trim_start: bool, trim_end: bool) {
+        if verbatim {
+            if !text.is_empty() {
+                self.write(*b"t");
+                self.write_str(text);
+            }
+            return;
+        }
+        let mut collapsed = String::with_capacity(text.len());
+        for ch in text.chars() {
+            if !ch.is_ascii_whitespace() {
+                collapsed.push(ch);
+            } else if !collapsed.ends_with(' ') {
+                collapsed.push(' ');
+            }
+        }
+        let mut text = collapsed.as_str();
+        if trim_start {
+            text = text.strip_prefix(' ').unwrap_or(text);
+        }
+        if trim_end {
+            text = text.strip_suffix(' ').unwrap_or(text);
+        }
+        if !text.is_empty() {
+            self.write(*b"t");
+            self.write_str(text);
+        }
+    }
+
+    fn element(&mut self, el: &Element, verbatim: bool) {
+        self.write(*b"<");
+        self.write_name(&el.name);
+
+        let mut attrs: Vec<(String, &str)> = el
+            .attrs
+            .iter()
+            .map(|(name, value)| (name.to_ascii_lowercase(), value.as_str()))
+            .collect();
+        attrs.sort();
+        self.write_len(attrs.len());
+        for (name, value) in attrs {
+            self.write_str(&name);
+            self.write_str(value);
+        }
+
+        let verbatim = verbatim
+            || ["pre", "textarea", "script", "style"]
+                .iter()
+                .any(|tag| el.name.eq_ignore_ascii_case(tag));
+        self.nodes(&el.children, verbatim, is_block(&el.name));
+        self.write(*b">");
+    }
+}
+
+// Whether whitespace around the element doesn't show, as for the block
+// elements and those of the document structure.
+fn is_block(name: &str) -> bool {
+    const OTHER_TAGS: [&str; 17] = [
+        "html", "head", "body", "title", "meta", "link", "base", "script", "style", "template",
+        "table", "thead", "tbody", "tfoot", "th", "td", "br",
+    ];
+    BLOCK_TAGS
+        .iter()
+        .chain(&OTHER_TAGS)
+        .any(|tag| name.eq_ignore_ascii_case(tag))
+}
@@ -2,10 +2,14 @@
 
 mod edit;
 mod html;
+mod inspect;
 mod query;
 mod selector;
 
-pub use edit::Editable;
-pub use html::Htmlifiable;
-pub use query::Queryable;
-pub use selector::Selector;
+pub use edit::{Editable, MapTextOptions};
+pub use html::{CustomSerializer, EmptyElements, Format, Htmlifiable, SerializeOptions};
+pub use inspect::{Inspectable, TreeStats};
+pub use query::{Comments, CommentsMut, NodePath, QueryIter, QueryOptions, Queryable};
+pub use selector::{Selector, SelectorParseError};
+
+pub(crate) use selector::{copy_elements, Scope, Siblings};
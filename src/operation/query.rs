@@ -1,10 +1,45 @@
-use super::Selector;
+use std::mem;
+
+use super::{Scope, Selector, SelectorParseError, Siblings};
 use crate::{Element, Node};
 
+/// Options to control how a query traverses the DOM.
+///
+/// ```
+/// use html_editor::operation::QueryOptions;
+///
+/// let options = QueryOptions {
+///     into_templates: true,
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QueryOptions {
+    /// Whether to look into the content of `<template>` elements.
+    ///
+    /// The content of a `<template>` is inert, so it is skipped by
+    /// default, just like `querySelectorAll` does.
+    pub into_templates: bool,
+}
+
+impl QueryOptions {
+    fn descends_into(&self, element: &Element) -> bool {
+        self.into_templates || !element.name.eq_ignore_ascii_case("template")
+    }
+}
+
+/// The position of a node in a DOM, as the indices of the children to go
+/// through from the top, see [`Queryable::query_all_paths`].
+///
+/// A path stays valid as long as the structure of the DOM is not modified,
+/// as adding or removing a node shifts the indices of its siblings.
+pub type NodePath = Vec<usize>;
+
 /// Query the specific element(s) by [`Selector`].
 pub trait Queryable {
     /// Query the node in `self` for the given selector.
     ///
+    /// The content of `<template>` elements is skipped, see [`QueryOptions`].
+    ///
     /// ```
     /// use html_editor::{parse, Element};
     /// use html_editor::operation::*;
@@ -26,7 +61,28 @@ pub trait Queryable {
     /// ```
     fn query(&self, selector: &Selector) -> Option<&Element>;
 
-    /// Query all the nodes in `self` for the given selector.
+    /// Same as [`query`](Queryable::query), but with the given
+    /// [`QueryOptions`].
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let html = r#"<template><span>Hidden</span></template>"#;
+    /// let nodes = parse(html).unwrap();
+    /// let selector = Selector::from("span");
+    ///
+    /// assert!(nodes.query(&selector).is_none());
+    ///
+    /// let options = QueryOptions { into_templates: true };
+    /// assert!(nodes.query_with(&selector, &options).is_some());
+    /// ```
+    fn query_with(&self, selector: &Selector, options: &QueryOptions) -> Option<&Element>;
+
+    /// Query all the nodes in `self` for the given selector, in document
+    /// order.
+    ///
+    /// The content of `<template>` elements is skipped, see [`QueryOptions`].
     ///
     /// ```
     /// use html_editor::{parse, Element};
@@ -49,10 +105,174 @@ pub trait Queryable {
     /// let selector: Selector = Selector::from(".btn");
     /// let buttons: Vec<&Element> = parse(html).unwrap().query_all(&selector);
     /// ```
+    ///
+    /// As ids should be unique, a selector consisting of a single id, like
+    /// `#app`, stops at the first match. So if the id is duplicated, only
+    /// the first element with it is returned.
     fn query_all(&self, selector: &Selector) -> Vec<&Element>;
 
+    /// Same as [`query_all`](Queryable::query_all), but with the given
+    /// [`QueryOptions`].
+    fn query_all_with(&self, selector: &Selector, options: &QueryOptions) -> Vec<&Element>;
+
+    /// Same as [`query`](Queryable::query), but the selector is given as a
+    /// string, which is parsed by [`Selector::parse`](Selector::parse).
+    ///
+    /// As the selector is parsed on each call, prefer parsing it once and
+    /// using [`query`](Queryable::query) when querying in a loop.
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let nodes = parse(r#"<nav><a class="link" href="/">Home</a></nav>"#).unwrap();
+    ///
+    /// let link = nodes.query_selector("nav a.link").unwrap().unwrap();
+    /// assert_eq!(link.get_attr("href"), Some("/"));
+    ///
+    /// assert!(nodes.query_selector("nav >").is_err());
+    /// ```
+    fn query_selector(&self, selector: &str) -> Result<Option<&Element>, SelectorParseError> {
+        Ok(self.query(&Selector::parse(selector)?))
+    }
+
+    /// Same as [`query_all`](Queryable::query_all), but the selector is
+    /// given as a string, like for [`query_selector`](Queryable::query_selector).
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let nodes = parse("<ul><li>1</li><li>2</li></ul>").unwrap();
+    /// let ul = nodes[0].as_element().unwrap();
+    ///
+    /// assert_eq!(ul.query_selector_all("li").unwrap().len(), 2);
+    /// ```
+    fn query_selector_all(&self, selector: &str) -> Result<Vec<&Element>, SelectorParseError> {
+        Ok(self.query_all(&Selector::parse(selector)?))
+    }
+
+    /// Query the paths to all the elements in `self` matching the given
+    /// selector, in document order, to be resolved by
+    /// [`element_at`](Queryable::element_at) or [`element_at_mut`](Queryable::element_at_mut).
+    ///
+    /// This allows editing the matching elements one at a time, when
+    /// [`execute_for`](Queryable::execute_for) is not enough. The paths
+    /// lead to the elements [`query_all`](Queryable::query_all) returns, so
+    /// the content of `<template>` elements is skipped, unless
+    /// [`query_all_paths_with`](Queryable::query_all_paths_with) says not to,
+    /// and only the first element is found for an id selector like `#main`.
+    ///
+    /// The paths go through the children, and an [`Element`] or a [`Node`]
+    /// itself has the empty path.
+    ///
+    /// ```
+    /// use html_editor::{parse, Node};
+    /// use html_editor::operation::*;
+    ///
+    /// let mut nodes = parse("<ul><li>1</li><li>2</li></ul><p>3</p>").unwrap();
+    /// let paths = nodes.query_all_paths(&Selector::from("li"));
+    /// assert_eq!(paths, vec![vec![0, 0], vec![0, 1]]);
+    ///
+    /// for path in paths {
+    ///     let li = nodes.element_at_mut(&path).unwrap();
+    ///     li.attrs.push(("class".to_string(), "item".to_string()));
+    /// }
+    /// assert_eq!(
+    ///     nodes.html(),
+    ///     r#"<ul><li class="item">1</li><li class="item">2</li></ul><p>3</p>"#
+    /// );
+    /// ```
+    fn query_all_paths(&self, selector: &Selector) -> Vec<NodePath>;
+
+    /// Same as [`query_all_paths`](Queryable::query_all_paths), but with the
+    /// given [`QueryOptions`], so that the paths lead to the elements
+    /// [`query_all_with`](Queryable::query_all_with) returns.
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let nodes = parse("<p>1</p><template><p>2</p></template>").unwrap();
+    /// let options = QueryOptions { into_templates: true };
+    /// let paths = nodes.query_all_paths_with(&Selector::from("p"), &options);
+    /// assert_eq!(paths, vec![vec![0], vec![1, 0]]);
+    /// ```
+    fn query_all_paths_with(&self, selector: &Selector, options: &QueryOptions) -> Vec<NodePath>;
+
+    /// Get the node at the given path in `self`, or `None` if there is no
+    /// such node, see [`query_all_paths`](Queryable::query_all_paths).
+    ///
+    /// It is not named `get`, so as not to hide [`slice::get`] on a
+    /// `Vec<Node>`. The empty path of an [`Element`] leads to the element
+    /// itself, which isn't a [`Node`], so it is only found by
+    /// [`element_at`](Queryable::element_at).
+    ///
+    /// ```
+    /// use html_editor::{parse, Node};
+    /// use html_editor::operation::*;
+    ///
+    /// let nodes = parse("<div><p>Hello</p></div>").unwrap();
+    ///
+    /// assert!(matches!(nodes.node_at(&[0, 0, 0]), Some(Node::Text(text)) if text == "Hello"));
+    /// assert!(nodes.node_at(&[0, 1]).is_none());
+    /// ```
+    fn node_at(&self, path: &[usize]) -> Option<&Node>;
+
+    /// Same as [`node_at`](Queryable::node_at), but the node is returned as
+    /// mutable.
+    fn node_at_mut(&mut self, path: &[usize]) -> Option<&mut Node>;
+
+    /// Get the element at the given path in `self`, or `None` if there is no
+    /// such element, see [`query_all_paths`](Queryable::query_all_paths).
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let nodes = parse("<div><p>Hello</p></div>").unwrap();
+    /// let div = nodes[0].as_element().unwrap();
+    ///
+    /// assert_eq!(div.element_at(&[]).unwrap().name, "div");
+    /// assert_eq!(div.element_at(&[0]).unwrap().name, "p");
+    /// assert!(div.element_at(&[0, 0]).is_none());
+    /// ```
+    fn element_at(&self, path: &[usize]) -> Option<&Element>;
+
+    /// Same as [`element_at`](Queryable::element_at), but the element is
+    /// returned as mutable.
+    fn element_at_mut(&mut self, path: &[usize]) -> Option<&mut Element>;
+
+    /// Lazily iterate over the elements in `self` matching the given
+    /// selector, in document order.
+    ///
+    /// Unlike [`query_all`](Queryable::query_all), the rest of the DOM is
+    /// not visited once the iterator is dropped.
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let nodes = parse("<ul><li>1</li><li>2</li><li>3</li></ul>").unwrap();
+    /// let selector = Selector::from("li");
+    ///
+    /// let second = nodes.query_iter(&selector).nth(1).unwrap();
+    /// assert_eq!(second.html(), "<li>2</li>");
+    /// ```
+    fn query_iter<'s>(&self, selector: &'s Selector) -> QueryIter<'_, 's>;
+
+    /// Same as [`query_iter`](Queryable::query_iter), but with the given
+    /// [`QueryOptions`].
+    fn query_iter_with<'s>(
+        &self,
+        selector: &'s Selector,
+        options: &QueryOptions,
+    ) -> QueryIter<'_, 's>;
+
     /// Query the node in `self` as mutable for the given selector.
     ///
+    /// The content of `<template>` elements is skipped, see [`QueryOptions`].
+    ///
     /// ```
     /// use html_editor::{parse, Element};
     /// use html_editor::operation::*;
@@ -74,8 +294,18 @@ pub trait Queryable {
     /// ```
     fn query_mut(&mut self, selector: &Selector) -> Option<&mut Element>;
 
+    /// Same as [`query_mut`](Queryable::query_mut), but with the given
+    /// [`QueryOptions`].
+    fn query_mut_with(
+        &mut self,
+        selector: &Selector,
+        options: &QueryOptions,
+    ) -> Option<&mut Element>;
+
     /// Executes a given function for the node in `self` for the given selector.
     ///
+    /// The content of `<template>` elements is skipped, see [`QueryOptions`].
+    ///
     /// ```
     /// use html_editor::{parse, Element, Node};
     /// use html_editor::operation::*;
@@ -102,132 +332,754 @@ pub trait Queryable {
     /// });
     /// ```
     fn execute_for(&mut self, selector: &Selector, f: impl FnMut(&mut Element));
+
+    /// Same as [`execute_for`](Queryable::execute_for), but with the given
+    /// [`QueryOptions`].
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let mut nodes = parse("<p>1</p><template><p>2</p></template>").unwrap();
+    /// let selector = Selector::from("p");
+    /// let into_templates = QueryOptions {
+    ///     into_templates: true,
+    /// };
+    ///
+    /// nodes.execute_for(&selector, |p| p.attrs.push(("class".into(), "a".into())));
+    /// nodes.execute_for_with(&selector, &into_templates, |p| p.attrs.push(("id".into(), "b".into())));
+    /// assert_eq!(
+    ///     nodes.html(),
+    ///     r#"<p class="a" id="b">1</p><template><p id="b">2</p></template>"#
+    /// );
+    /// ```
+    fn execute_for_with(
+        &mut self,
+        selector: &Selector,
+        options: &QueryOptions,
+        f: impl FnMut(&mut Element),
+    );
+
+    /// Find the first element in `self`, in document order, for which the
+    /// given predicate returns `true`, and return it as mutable.
+    ///
+    /// The traversal stops at the first match. Unlike
+    /// [`query_mut`](Queryable::query_mut), the content of `<template>`
+    /// elements is visited as well.
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let html = r#"<p>One</p><p data-edit>Two</p><p data-edit>Three</p>"#;
+    /// let mut nodes = parse(html).unwrap();
+    ///
+    /// let p = nodes
+    ///     .find_first_mut(|el| el.attrs.iter().any(|(k, _)| k == "data-edit"))
+    ///     .unwrap();
+    /// p.attrs.clear();
+    ///
+    /// assert_eq!(nodes.html(), r#"<p>One</p><p>Two</p><p data-edit="">Three</p>"#);
+    /// ```
+    fn find_first_mut(&mut self, f: impl FnMut(&Element) -> bool) -> Option<&mut Element>;
+
+    /// Iterate over the content of all the comments in `self`, in document
+    /// order, including the ones in `<template>` elements.
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let html = r#"<!-- build:js --><script src="a.js"></script><div><!-- endbuild --></div>"#;
+    /// let nodes = parse(html).unwrap();
+    /// let comments: Vec<&str> = nodes.comments().collect();
+    ///
+    /// assert_eq!(comments, vec![" build:js ", " endbuild "]);
+    /// ```
+    fn comments(&self) -> Comments<'_>;
+
+    /// Same as [`comments`](Queryable::comments), but the comments can be
+    /// edited.
+    ///
+    /// ```
+    /// use html_editor::parse;
+    /// use html_editor::operation::*;
+    ///
+    /// let mut nodes = parse("<p><!-- TODO --></p>").unwrap();
+    /// for comment in nodes.comments_mut() {
+    ///     *comment = comment.replace("TODO", "DONE");
+    /// }
+    ///
+    /// assert_eq!(nodes.html(), "<p><!-- DONE --></p>");
+    /// ```
+    fn comments_mut(&mut self) -> CommentsMut<'_>;
 }
 
-// We meed this function to allow the trait interface to use `impl FnMut(&mut Element)` instead of `&mut impl FnMut(&mut Element)`
-fn nodes_execute_for_internal(
-    nodes: &mut Vec<Node>,
+/// A lazy iterator over the elements matching a selector, see
+/// [`Queryable::query_iter`](Queryable::query_iter).
+pub struct QueryIter<'a, 's> {
+    selector: &'s Selector,
+    options: QueryOptions,
+    /// The element to visit first, when querying an element.
+    root: Option<&'a Element>,
+    /// The elements whose children are being visited, outermost first.
+    ancestors: Vec<(&'a Element, Siblings<'a>)>,
+    /// The children of each of the ancestors, with the index of the next
+    /// one to visit. When querying nodes, the top-level ones come first.
+    stack: Vec<(&'a [Node], usize)>,
+}
+
+impl<'a, 's> QueryIter<'a, 's> {
+    fn new(nodes: &'a [Node], selector: &'s Selector, options: &QueryOptions) -> Self {
+        QueryIter {
+            selector,
+            options: options.clone(),
+            root: None,
+            ancestors: Vec::new(),
+            stack: vec![(nodes, 0)],
+        }
+    }
+
+    fn with_root(element: &'a Element, selector: &'s Selector, options: &QueryOptions) -> Self {
+        QueryIter {
+            selector,
+            options: options.clone(),
+            root: Some(element),
+            ancestors: Vec::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    /// Check if `element` matches, and enter its children.
+    fn visit(&mut self, element: &'a Element, siblings: Siblings<'a>) -> bool {
+        let matched =
+            self.selector
+                .matches_in(&Scope::with_ancestors(element, siblings, &self.ancestors));
+        if self.options.descends_into(element) {
+            self.ancestors.push((element, siblings));
+            self.stack.push((&element.children, 0));
+        }
+        matched
+    }
+}
+
+impl<'a> Iterator for QueryIter<'a, '_> {
+    type Item = &'a Element;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.root.take() {
+            if self.visit(root, Siblings::None) {
+                return Some(root);
+            }
+        }
+        loop {
+            let (nodes, index) = self.stack.last_mut()?;
+            let nodes: &'a [Node] = nodes;
+            match nodes.get(*index) {
+                Some(node) => {
+                    let siblings = Siblings::Nodes(nodes, *index);
+                    *index += 1;
+                    if let Node::Element(element) = node {
+                        if self.visit(element, siblings) {
+                            return Some(element);
+                        }
+                    }
+                }
+                None => {
+                    self.stack.pop();
+                    self.ancestors.pop();
+                }
+            }
+        }
+    }
+}
+
+/// An iterator over the comments of a DOM, see
+/// [`Queryable::comments`](Queryable::comments).
+pub struct Comments<'a> {
+    stack: Vec<std::slice::Iter<'a, Node>>,
+}
+
+impl<'a> Iterator for Comments<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let iter = self.stack.last_mut()?;
+            match iter.next() {
+                Some(Node::Comment(comment)) => return Some(comment),
+                Some(Node::Element(el)) => self.stack.push(el.children.iter()),
+                Some(_) => {}
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// An iterator over the mutable comments of a DOM, see
+/// [`Queryable::comments_mut`](Queryable::comments_mut).
+pub struct CommentsMut<'a> {
+    stack: Vec<std::slice::IterMut<'a, Node>>,
+}
+
+impl<'a> Iterator for CommentsMut<'a> {
+    type Item = &'a mut String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let iter = self.stack.last_mut()?;
+            match iter.next() {
+                Some(Node::Comment(comment)) => return Some(comment),
+                Some(Node::Element(el)) => self.stack.push(el.children.iter_mut()),
+                Some(_) => {}
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+fn nodes_query<'a>(
+    nodes: &'a [Node],
+    parent: Option<&Scope>,
     selector: &Selector,
-    f: &mut impl FnMut(&mut Element),
+    options: &QueryOptions,
+) -> Option<&'a Element> {
+    nodes.iter().enumerate().find_map(|(index, node)| {
+        let element = node.as_element()?;
+        let scope = Scope::new(element, Siblings::Nodes(nodes, index), parent);
+        element_query(element, &scope, selector, options)
+    })
+}
+
+fn element_query<'a>(
+    element: &'a Element,
+    scope: &Scope,
+    selector: &Selector,
+    options: &QueryOptions,
+) -> Option<&'a Element> {
+    if selector.matches_in(scope) {
+        Some(element)
+    } else if options.descends_into(element) {
+        nodes_query(&element.children, Some(scope), selector, options)
+    } else {
+        None
+    }
+}
+
+// An id selector only finds the first element, as in `query_all`.
+fn nodes_query_paths(
+    nodes: &[Node],
+    parent: Option<&Scope>,
+    selector: &Selector,
+    options: &QueryOptions,
+    path: &mut NodePath,
+    paths: &mut Vec<NodePath>,
 ) {
-    for node in nodes {
-        if let Some(element) = node.as_element_mut() {
-            // Recursively traverse the descendants nodes
-            element_execute_for_internal(element, selector, f);
+    for (index, node) in nodes.iter().enumerate() {
+        if selector.is_single_id() && !paths.is_empty() {
+            return;
+        }
+        if let Node::Element(element) = node {
+            path.push(index);
+            let scope = Scope::new(element, Siblings::Nodes(nodes, index), parent);
+            element_query_paths(element, &scope, selector, options, path, paths);
+            path.pop();
         }
     }
 }
 
+fn element_query_paths(
+    element: &Element,
+    scope: &Scope,
+    selector: &Selector,
+    options: &QueryOptions,
+    path: &mut NodePath,
+    paths: &mut Vec<NodePath>,
+) {
+    if selector.matches_in(scope) {
+        paths.push(path.clone());
+        if selector.is_single_id() {
+            return;
+        }
+    }
+    if options.descends_into(element) {
+        nodes_query_paths(
+            &element.children,
+            Some(scope),
+            selector,
+            options,
+            path,
+            paths,
+        );
+    }
+}
+
+fn nodes_node_at<'a>(nodes: &'a [Node], path: &[usize]) -> Option<&'a Node> {
+    let (index, rest) = path.split_first()?;
+    let node = nodes.get(*index)?;
+    if rest.is_empty() {
+        Some(node)
+    } else {
+        nodes_node_at(&node.as_element()?.children, rest)
+    }
+}
+
+fn element_at<'a>(element: &'a Element, path: &[usize]) -> Option<&'a Element> {
+    match path {
+        [] => Some(element),
+        path => nodes_node_at(&element.children, path)?.as_element(),
+    }
+}
+
+fn element_at_mut<'a>(element: &'a mut Element, path: &[usize]) -> Option<&'a mut Element> {
+    match path {
+        [] => Some(element),
+        path => nodes_node_at_mut(&mut element.children, path)?.as_element_mut(),
+    }
+}
+
+fn nodes_node_at_mut<'a>(nodes: &'a mut [Node], path: &[usize]) -> Option<&'a mut Node> {
+    let (index, rest) = path.split_first()?;
+    let node = nodes.get_mut(*index)?;
+    if rest.is_empty() {
+        Some(node)
+    } else {
+        nodes_node_at_mut(&mut node.as_element_mut()?.children, rest)
+    }
+}
+
+// The mutable versions below match the elements before borrowing them as
+// mutable, as their ancestors cannot be borrowed while they are.
+
+fn nodes_query_path(
+    nodes: &[Node],
+    parent: Option<&Scope>,
+    selector: &Selector,
+    options: &QueryOptions,
+    path: &mut NodePath,
+) -> bool {
+    nodes.iter().enumerate().any(|(index, node)| match node {
+        Node::Element(element) => {
+            path.push(index);
+            let scope = Scope::new(element, Siblings::Nodes(nodes, index), parent);
+            let found = element_query_path(element, &scope, selector, options, path);
+            if !found {
+                path.pop();
+            }
+            found
+        }
+        _ => false,
+    })
+}
+
+fn element_query_path(
+    element: &Element,
+    scope: &Scope,
+    selector: &Selector,
+    options: &QueryOptions,
+    path: &mut NodePath,
+) -> bool {
+    selector.matches_in(scope)
+        || options.descends_into(element)
+            && nodes_query_path(&element.children, Some(scope), selector, options, path)
+}
+
 // We meed this function to allow the trait interface to use `impl FnMut(&mut Element)` instead of `&mut impl FnMut(&mut Element)`
-fn element_execute_for_internal(
-    element: &mut Element,
+fn nodes_execute_for_internal(
+    nodes: &mut [Node],
+    parent: Option<&Scope>,
     selector: &Selector,
+    options: &QueryOptions,
     f: &mut impl FnMut(&mut Element),
 ) {
-    if selector.matches(element) {
-        f(element);
+    for index in 0..nodes.len() {
+        let matched = match &nodes[index] {
+            Node::Element(element) => {
+                selector.matches_in(&Scope::new(element, Siblings::Nodes(nodes, index), parent))
+            }
+            _ => continue,
+        };
+        if let Node::Element(element) = &mut nodes[index] {
+            if matched {
+                f(element);
+            }
+            if !options.descends_into(element) {
+                continue;
+            }
+            // Take the children out, so that the element can be matched
+            // as their ancestor while they are borrowed as mutable.
+            let mut children = mem::take(&mut element.children);
+            if let Node::Element(element) = &nodes[index] {
+                let scope = Scope::new(element, Siblings::Nodes(nodes, index), parent);
+                // Recursively traverse the descendants nodes
+                nodes_execute_for_internal(&mut children, Some(&scope), selector, options, f);
+            }
+            if let Node::Element(element) = &mut nodes[index] {
+                element.children = children;
+            }
+        }
+    }
+}
+
+fn nodes_find_first_mut<'a>(
+    nodes: &'a mut [Node],
+    f: &mut impl FnMut(&Element) -> bool,
+) -> Option<&'a mut Element> {
+    nodes
+        .iter_mut()
+        .filter_map(Node::as_element_mut)
+        .find_map(|element| element_find_first_mut(element, f))
+}
+
+fn element_find_first_mut<'a>(
+    element: &'a mut Element,
+    f: &mut impl FnMut(&Element) -> bool,
+) -> Option<&'a mut Element> {
+    if f(element) {
+        Some(element)
+    } else {
+        nodes_find_first_mut(&mut element.children, f)
     }
-    nodes_execute_for_internal(&mut element.children, selector, f);
 }
 
 impl Queryable for Vec<Node> {
     fn query(&self, selector: &Selector) -> Option<&Element> {
-        for node in self {
-            if let Some(element) = node.as_element() {
-                if let Some(elem) = element.query(selector) {
-                    return Some(elem);
-                }
-            }
-        }
-        None
+        self.query_with(selector, &QueryOptions::default())
+    }
+
+    fn query_with(&self, selector: &Selector, options: &QueryOptions) -> Option<&Element> {
+        nodes_query(self, None, selector, options)
     }
 
     fn query_all(&self, selector: &Selector) -> Vec<&Element> {
-        let mut elements = Vec::new();
-        for node in self {
-            if let Some(element) = node.as_element() {
-                // Recursively traverse the descendants nodes
-                let sub_elements = element.query_all(selector);
-                elements.extend(sub_elements);
-            }
+        self.query_all_with(selector, &QueryOptions::default())
+    }
+
+    fn query_all_with(&self, selector: &Selector, options: &QueryOptions) -> Vec<&Element> {
+        if selector.is_single_id() {
+            return self.query_with(selector, options).into_iter().collect();
         }
-        elements
+        self.query_iter_with(selector, options).collect()
+    }
+
+    fn query_all_paths(&self, selector: &Selector) -> Vec<NodePath> {
+        self.query_all_paths_with(selector, &QueryOptions::default())
+    }
+
+    fn query_all_paths_with(&self, selector: &Selector, options: &QueryOptions) -> Vec<NodePath> {
+        let mut paths = Vec::new();
+        nodes_query_paths(self, None, selector, options, &mut Vec::new(), &mut paths);
+        paths
+    }
+
+    fn node_at(&self, path: &[usize]) -> Option<&Node> {
+        nodes_node_at(self, path)
+    }
+
+    fn node_at_mut(&mut self, path: &[usize]) -> Option<&mut Node> {
+        nodes_node_at_mut(self, path)
+    }
+
+    fn element_at(&self, path: &[usize]) -> Option<&Element> {
+        self.node_at(path)?.as_element()
+    }
+
+    fn element_at_mut(&mut self, path: &[usize]) -> Option<&mut Element> {
+        self.node_at_mut(path)?.as_element_mut()
+    }
+
+    fn query_iter<'s>(&self, selector: &'s Selector) -> QueryIter<'_, 's> {
+        self.query_iter_with(selector, &QueryOptions::default())
+    }
+
+    fn query_iter_with<'s>(
+        &self,
+        selector: &'s Selector,
+        options: &QueryOptions,
+    ) -> QueryIter<'_, 's> {
+        QueryIter::new(self, selector, options)
     }
 
     fn query_mut(&mut self, selector: &Selector) -> Option<&mut Element> {
-        for node in self {
-            if let Some(element) = node.as_element_mut() {
-                if let Some(elem) = element.query_mut(selector) {
-                    return Some(elem);
-                }
-            }
+        self.query_mut_with(selector, &QueryOptions::default())
+    }
+
+    fn query_mut_with(
+        &mut self,
+        selector: &Selector,
+        options: &QueryOptions,
+    ) -> Option<&mut Element> {
+        let mut path = NodePath::new();
+        if nodes_query_path(self, None, selector, options, &mut path) {
+            nodes_node_at_mut(self, &path)?.as_element_mut()
+        } else {
+            None
         }
-        None
     }
 
-    fn execute_for(&mut self, selector: &Selector, mut f: impl FnMut(&mut Element)) {
-        nodes_execute_for_internal(self, selector, &mut f);
+    fn execute_for(&mut self, selector: &Selector, f: impl FnMut(&mut Element)) {
+        self.execute_for_with(selector, &QueryOptions::default(), f);
+    }
+
+    fn execute_for_with(
+        &mut self,
+        selector: &Selector,
+        options: &QueryOptions,
+        mut f: impl FnMut(&mut Element),
+    ) {
+        nodes_execute_for_internal(self, None, selector, options, &mut f);
+    }
+
+    fn find_first_mut(&mut self, mut f: impl FnMut(&Element) -> bool) -> Option<&mut Element> {
+        nodes_find_first_mut(self, &mut f)
+    }
+
+    fn comments(&self) -> Comments<'_> {
+        Comments {
+            stack: vec![self.iter()],
+        }
+    }
+
+    fn comments_mut(&mut self) -> CommentsMut<'_> {
+        CommentsMut {
+            stack: vec![self.iter_mut()],
+        }
     }
 }
 
 impl Queryable for Element {
     fn query(&self, selector: &Selector) -> Option<&Element> {
-        if selector.matches(self) {
-            Some(self)
-        } else {
-            self.children.query(selector)
-        }
+        self.query_with(selector, &QueryOptions::default())
+    }
+
+    fn query_with(&self, selector: &Selector, options: &QueryOptions) -> Option<&Element> {
+        element_query(self, &Scope::root(self), selector, options)
     }
 
     fn query_all(&self, selector: &Selector) -> Vec<&Element> {
-        let mut elements = self.children.query_all(selector);
-        if selector.matches(self) {
-            elements.push(self);
+        self.query_all_with(selector, &QueryOptions::default())
+    }
+
+    fn query_all_with(&self, selector: &Selector, options: &QueryOptions) -> Vec<&Element> {
+        if selector.is_single_id() {
+            return self.query_with(selector, options).into_iter().collect();
         }
-        elements
+        self.query_iter_with(selector, options).collect()
+    }
+
+    fn query_all_paths(&self, selector: &Selector) -> Vec<NodePath> {
+        self.query_all_paths_with(selector, &QueryOptions::default())
+    }
+
+    fn query_all_paths_with(&self, selector: &Selector, options: &QueryOptions) -> Vec<NodePath> {
+        let mut paths = Vec::new();
+        let scope = Scope::root(self);
+        element_query_paths(self, &scope, selector, options, &mut Vec::new(), &mut paths);
+        paths
+    }
+
+    fn node_at(&self, path: &[usize]) -> Option<&Node> {
+        nodes_node_at(&self.children, path)
+    }
+
+    fn node_at_mut(&mut self, path: &[usize]) -> Option<&mut Node> {
+        nodes_node_at_mut(&mut self.children, path)
+    }
+
+    fn element_at(&self, path: &[usize]) -> Option<&Element> {
+        element_at(self, path)
+    }
+
+    fn element_at_mut(&mut self, path: &[usize]) -> Option<&mut Element> {
+        element_at_mut(self, path)
+    }
+
+    fn query_iter<'s>(&self, selector: &'s Selector) -> QueryIter<'_, 's> {
+        self.query_iter_with(selector, &QueryOptions::default())
+    }
+
+    fn query_iter_with<'s>(
+        &self,
+        selector: &'s Selector,
+        options: &QueryOptions,
+    ) -> QueryIter<'_, 's> {
+        QueryIter::with_root(self, selector, options)
     }
 
     fn query_mut(&mut self, selector: &Selector) -> Option<&mut Element> {
-        if selector.matches(self) {
-            Some(self)
+        self.query_mut_with(selector, &QueryOptions::default())
+    }
+
+    fn query_mut_with(
+        &mut self,
+        selector: &Selector,
+        options: &QueryOptions,
+    ) -> Option<&mut Element> {
+        let mut path = NodePath::new();
+        if element_query_path(self, &Scope::root(self), selector, options, &mut path) {
+            element_at_mut(self, &path)
         } else {
-            self.children.query_mut(selector)
+            None
+        }
+    }
+
+    fn execute_for(&mut self, selector: &Selector, f: impl FnMut(&mut Element)) {
+        self.execute_for_with(selector, &QueryOptions::default(), f);
+    }
+
+    fn execute_for_with(
+        &mut self,
+        selector: &Selector,
+        options: &QueryOptions,
+        mut f: impl FnMut(&mut Element),
+    ) {
+        if selector.matches_in(&Scope::root(self)) {
+            f(self);
         }
+        if options.descends_into(self) {
+            let mut children = mem::take(&mut self.children);
+            let scope = Scope::root(self);
+            nodes_execute_for_internal(&mut children, Some(&scope), selector, options, &mut f);
+            self.children = children;
+        }
+    }
+
+    fn find_first_mut(&mut self, mut f: impl FnMut(&Element) -> bool) -> Option<&mut Element> {
+        element_find_first_mut(self, &mut f)
+    }
+
+    fn comments(&self) -> Comments<'_> {
+        self.children.comments()
     }
 
-    fn execute_for(&mut self, selector: &Selector, mut f: impl FnMut(&mut Element)) {
-        element_execute_for_internal(self, selector, &mut f);
+    fn comments_mut(&mut self) -> CommentsMut<'_> {
+        self.children.comments_mut()
     }
 }
 
 impl Queryable for Node {
     fn query(&self, selector: &Selector) -> Option<&Element> {
+        self.query_with(selector, &QueryOptions::default())
+    }
+
+    fn query_with(&self, selector: &Selector, options: &QueryOptions) -> Option<&Element> {
         if let Some(element) = self.as_element() {
-            element.query(selector)
+            element.query_with(selector, options)
         } else {
             None
         }
     }
 
     fn query_all(&self, selector: &Selector) -> Vec<&Element> {
+        self.query_all_with(selector, &QueryOptions::default())
+    }
+
+    fn query_all_with(&self, selector: &Selector, options: &QueryOptions) -> Vec<&Element> {
         if let Some(element) = self.as_element() {
-            element.query_all(selector)
+            element.query_all_with(selector, options)
         } else {
             Vec::new()
         }
     }
 
+    fn query_all_paths(&self, selector: &Selector) -> Vec<NodePath> {
+        self.query_all_paths_with(selector, &QueryOptions::default())
+    }
+
+    fn query_all_paths_with(&self, selector: &Selector, options: &QueryOptions) -> Vec<NodePath> {
+        match self.as_element() {
+            Some(element) => element.query_all_paths_with(selector, options),
+            None => Vec::new(),
+        }
+    }
+
+    fn node_at(&self, path: &[usize]) -> Option<&Node> {
+        if path.is_empty() {
+            Some(self)
+        } else {
+            nodes_node_at(&self.as_element()?.children, path)
+        }
+    }
+
+    fn node_at_mut(&mut self, path: &[usize]) -> Option<&mut Node> {
+        if path.is_empty() {
+            Some(self)
+        } else {
+            nodes_node_at_mut(&mut self.as_element_mut()?.children, path)
+        }
+    }
+
+    fn element_at(&self, path: &[usize]) -> Option<&Element> {
+        element_at(self.as_element()?, path)
+    }
+
+    fn element_at_mut(&mut self, path: &[usize]) -> Option<&mut Element> {
+        element_at_mut(self.as_element_mut()?, path)
+    }
+
+    fn query_iter<'s>(&self, selector: &'s Selector) -> QueryIter<'_, 's> {
+        self.query_iter_with(selector, &QueryOptions::default())
+    }
+
+    fn query_iter_with<'s>(
+        &self,
+        selector: &'s Selector,
+        options: &QueryOptions,
+    ) -> QueryIter<'_, 's> {
+        match self.as_element() {
+            Some(element) => element.query_iter_with(selector, options),
+            None => QueryIter::new(&[], selector, options),
+        }
+    }
+
     fn query_mut(&mut self, selector: &Selector) -> Option<&mut Element> {
+        self.query_mut_with(selector, &QueryOptions::default())
+    }
+
+    fn query_mut_with(
+        &mut self,
+        selector: &Selector,
+        options: &QueryOptions,
+    ) -> Option<&mut Element> {
         if let Some(element) = self.as_element_mut() {
-            element.query_mut(selector)
+            element.query_mut_with(selector, options)
         } else {
             None
         }
     }
 
     fn execute_for(&mut self, selector: &Selector, f: impl FnMut(&mut Element)) {
+        self.execute_for_with(selector, &QueryOptions::default(), f);
+    }
+
+    fn execute_for_with(
+        &mut self,
+        selector: &Selector,
+        options: &QueryOptions,
+        f: impl FnMut(&mut Element),
+    ) {
         if let Some(element) = self.as_element_mut() {
-            element.execute_for(selector, f);
+            element.execute_for_with(selector, options, f);
+        }
+    }
+
+    fn find_first_mut(&mut self, f: impl FnMut(&Element) -> bool) -> Option<&mut Element> {
+        self.as_element_mut()?.find_first_mut(f)
+    }
+
+    fn comments(&self) -> Comments<'_> {
+        Comments {
+            stack: vec![std::slice::from_ref(self).iter()],
+        }
+    }
+
+    fn comments_mut(&mut self) -> CommentsMut<'_> {
+        CommentsMut {
+            stack: vec![std::slice::from_mut(self).iter_mut()],
         }
     }
 }
@@ -1,9 +1,16 @@
 mod compound;
+mod nth;
+mod parser;
 mod simple;
 
-use crate::Element;
+use std::str::FromStr;
 
-use self::{compound::CompoundSelector, simple::SimpleSelector};
+use crate::{Element, Node};
+
+use self::compound::CompoundSelector;
+use self::simple::SimpleSelector;
+
+pub use self::parser::SelectorParseError;
 
 /// Basic selector. It follows the
 /// [CSS selector](https://developer.mozilla.org/en-US/docs/Web/CSS/CSS_Selectors)
@@ -12,9 +19,164 @@ use self::{compound::CompoundSelector, simple::SimpleSelector};
 #[derive(Debug)]
 pub struct Selector(Vec<CompoundSelector>);
 
+/// An element along with its siblings and ancestors, which is what the
+/// combinators and structural pseudo-classes need to know to match it.
+#[derive(Clone, Copy)]
+pub(crate) struct Scope<'a> {
+    pub element: &'a Element,
+    siblings: Siblings<'a>,
+    ancestors: Ancestors<'a>,
+}
+
+/// The siblings of an element, including itself.
+#[derive(Clone, Copy)]
+pub(crate) enum Siblings<'a> {
+    /// The siblings are unknown, or there are none, so the element is
+    /// treated as an only child.
+    None,
+    /// The nodes the element is in, and its index among them.
+    Nodes(&'a [Node], usize),
+    /// Copies of the sibling elements, see [`copy_elements`], and the
+    /// index of the element among them.
+    Elements(&'a [Element], usize),
+    /// Copies of the preceding sibling elements followed by the element,
+    /// when the following ones are not known yet.
+    Preceding(&'a [Element]),
+}
+
+#[derive(Clone, Copy)]
+enum Ancestors<'a> {
+    /// The scope of the parent, when the DOM is traversed recursively.
+    Parent(Option<&'a Scope<'a>>),
+    /// The ancestors, outermost first, when it is traversed with a stack.
+    Stack(&'a [(&'a Element, Siblings<'a>)]),
+}
+
+impl<'a> Scope<'a> {
+    pub fn new(
+        element: &'a Element,
+        siblings: Siblings<'a>,
+        parent: Option<&'a Scope<'a>>,
+    ) -> Self {
+        Scope {
+            element,
+            siblings,
+            ancestors: Ancestors::Parent(parent),
+        }
+    }
+
+    /// The scope of an element whose siblings and ancestors are unknown,
+    /// or which has none.
+    pub fn root(element: &'a Element) -> Self {
+        Scope::new(element, Siblings::None, None)
+    }
+
+    pub fn child(&'a self, element: &'a Element, siblings: Siblings<'a>) -> Self {
+        Scope::new(element, siblings, Some(self))
+    }
+
+    pub fn with_ancestors(
+        element: &'a Element,
+        siblings: Siblings<'a>,
+        ancestors: &'a [(&'a Element, Siblings<'a>)],
+    ) -> Self {
+        Scope {
+            element,
+            siblings,
+            ancestors: Ancestors::Stack(ancestors),
+        }
+    }
+
+    pub fn parent(&self) -> Option<Scope<'a>> {
+        match self.ancestors {
+            Ancestors::Parent(parent) => parent.copied(),
+            Ancestors::Stack(ancestors) => {
+                ancestors
+                    .split_last()
+                    .map(|(&(parent, siblings), ancestors)| {
+                        Scope::with_ancestors(parent, siblings, ancestors)
+                    })
+            }
+        }
+    }
+
+    /// Count the sibling elements before and after the element. The count
+    /// after is `None` if the following siblings are not known.
+    pub fn siblings(&self) -> (usize, Option<usize>) {
+        self.count_siblings(|_| true)
+    }
+
+    /// Same as [`siblings`](Scope::siblings), but only the siblings which
+    /// have the same type, that is the same tag name, are counted.
+    pub fn siblings_of_type(&self) -> (usize, Option<usize>) {
+        let name = &self.element.name;
+        self.count_siblings(|element| element.name.eq_ignore_ascii_case(name))
+    }
+
+    fn count_siblings(&self, f: impl Fn(&Element) -> bool) -> (usize, Option<usize>) {
+        let counted = |element: &&Element| f(element);
+        match self.siblings {
+            Siblings::None => (0, Some(0)),
+            Siblings::Nodes(nodes, index) => {
+                let count = |nodes: &[Node]| {
+                    nodes
+                        .iter()
+                        .filter_map(Node::as_element)
+                        .filter(counted)
+                        .count()
+                };
+                (count(&nodes[..index]), Some(count(&nodes[index + 1..])))
+            }
+            Siblings::Elements(elements, index) => {
+                let count = |elements: &[Element]| elements.iter().filter(counted).count();
+                (
+                    count(&elements[..index]),
+                    Some(count(&elements[index + 1..])),
+                )
+            }
+            Siblings::Preceding(elements) => {
+                let before = &elements[..elements.len().saturating_sub(1)];
+                (before.iter().filter(counted).count(), None)
+            }
+        }
+    }
+}
+
+/// Copy the elements among `nodes` without their children, so that they
+/// can be matched while the nodes are borrowed as mutable.
+pub(crate) fn copy_elements(nodes: &[Node]) -> Vec<Element> {
+    nodes
+        .iter()
+        .filter_map(Node::as_element)
+        .map(Element::shallow_clone)
+        .collect()
+}
+
 impl Selector {
+    /// Parse a selector, like [`Selector::from`](Selector::from) does, but
+    /// return an error if it is invalid or not supported, instead of a
+    /// selector which matches nothing.
+    ///
+    /// ```
+    /// use html_editor::operation::{Selector, SelectorParseError};
+    ///
+    /// assert!(Selector::parse("ul > li.active").is_ok());
+    /// assert_eq!(
+    ///     Selector::parse("h1,,h2").unwrap_err(),
+    ///     SelectorParseError::UnexpectedChar(',')
+    /// );
+    /// ```
+    pub fn parse(selector: &str) -> Result<Selector, SelectorParseError> {
+        parser::parse(selector)
+    }
+
     /// Check if the `element` matches the `selector`.
     ///
+    /// As only the element itself is given, selectors involving its
+    /// ancestors, like `section p`, never match here, and `:root` always
+    /// does. The element counts as having no siblings, so `:first-of-type`
+    /// matches it too.
+    ///
     /// ```
     /// use html_editor::{Node, Element};
     /// use html_editor::operation::*;
@@ -30,35 +192,29 @@ impl Selector {
     /// assert_eq!(selector.matches(&element), true);
     /// ```
     pub fn matches(&self, element: &Element) -> bool {
-        let element_classes = element
-            .attrs
-            .iter()
-            .find(|(key, _)| key == "class")
-            .map(|(_, v)| v.split(' ').map(|name| name.trim()).collect::<Vec<_>>());
-        let element_id = element
-            .attrs
+        self.matches_in(&Scope::root(element))
+    }
+
+    /// Whether the selector consists of a single id, like `#main`.
+    pub(crate) fn is_single_id(&self) -> bool {
+        match self.0.as_slice() {
+            [CompoundSelector(simple_selectors)] => {
+                matches!(simple_selectors.as_slice(), [SimpleSelector::Id(_)])
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether matching the selector depends on the siblings of the
+    /// elements, like `:first-of-type` does.
+    pub(crate) fn needs_siblings(&self) -> bool {
+        self.0.iter().any(CompoundSelector::needs_siblings)
+    }
+
+    pub(crate) fn matches_in(&self, scope: &Scope) -> bool {
+        self.0
             .iter()
-            .find(|(key, _)| key == "id")
-            .map(|(_, v)| v);
-
-        self.0.iter().any(|compound_selector| {
-            compound_selector
-                .0
-                .iter()
-                .all(|simple_selector| match simple_selector {
-                    SimpleSelector::Class(selector_class) => match &element_classes {
-                        Some(element_classes) => element_classes
-                            .iter()
-                            .any(|element_class| element_class == selector_class),
-                        None => false,
-                    },
-                    SimpleSelector::Id(selector_id) => match element_id {
-                        Some(element_id) => element_id == selector_id,
-                        None => false,
-                    },
-                    SimpleSelector::Tag(tag) => tag == &element.name,
-                })
-        })
+            .any(|compound_selector| compound_selector.matches(scope))
     }
 }
 
@@ -72,7 +228,7 @@ impl From<&str> for Selector {
     /// ```
     /// use html_editor::operation::Selector;
     ///
-    /// // Type Selector
+    /// // Type Selector, which matches `<SPAN>` too
     /// Selector::from("span");
     /// // Class selector
     /// Selector::from(".class");
@@ -83,12 +239,44 @@ impl From<&str> for Selector {
     /// Selector::from("h1, h2");
     /// // Compound selector
     /// Selector::from("input.username");
-    ///
-    /// // Disallowed input that may cause unexpected result
+    /// // Descendant combinator
     /// Selector::from("div span");
-    /// Selector::from("a[target=_blank]");
+    /// // Child combinator
+    /// Selector::from("ul > li");
+    /// // Matches-any pseudo-classes
+    /// Selector::from("article :is(h1, h2)");
+    /// Selector::from(":where(ul, ol) li");
+    /// // Root pseudo-class, which matches the outermost elements
+    /// Selector::from(":root > body");
+    /// // Structural pseudo-classes, which count the sibling elements, or
+    /// // the ones of the same type
+    /// Selector::from("li:nth-child(odd)");
+    /// Selector::from("li:first-child, li:nth-last-child(-n+2)");
+    /// Selector::from("p:nth-of-type(2n+1)");
+    /// Selector::from("tr:nth-last-of-type(2)");
+    /// Selector::from("li:first-of-type, li:last-of-type");
+    ///
+    /// // Attribute selectors, with the `i` flag to ignore the case of the value
+    /// Selector::from("a[href]");
+    /// Selector::from(r#"a[target="_blank"]"#);
+    /// Selector::from("input[type=checkbox i]");
+    ///
+    /// // Unsupported rules, which match no element at all
+    /// Selector::from("h1 + p");
     /// ```
+    ///
+    /// The same goes for invalid selectors. Use
+    /// [`Selector::parse`](Selector::parse) to get an error instead.
     fn from(selector: &str) -> Self {
-        Selector(selector.split(',').map(CompoundSelector::from).collect())
+        parser::parse(selector).unwrap_or(Selector(vec![]))
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorParseError;
+
+    /// Same as [`Selector::parse`](Selector::parse).
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        Selector::parse(selector)
     }
 }
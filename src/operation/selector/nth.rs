@@ -0,0 +1,53 @@
+/// The `An+B` argument of pseudo-classes like `:nth-of-type()`, which
+/// matches every `a`-th element starting from the `b`-th one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Nth {
+    pub a: i32,
+    pub b: i32,
+}
+
+impl Nth {
+    /// Matches the first element only, as in `:first-of-type`.
+    pub const FIRST: Nth = Nth { a: 0, b: 1 };
+
+    /// Parse the argument, like `odd`, `3`, `2n+1` or `-n + 3`.
+    pub fn parse(input: &str) -> Option<Nth> {
+        let input = input.trim().to_ascii_lowercase();
+        match input.as_str() {
+            "odd" => return Some(Nth { a: 2, b: 1 }),
+            "even" => return Some(Nth { a: 2, b: 0 }),
+            _ => {}
+        }
+        let Some(n) = input.find('n') else {
+            return Some(Nth {
+                a: 0,
+                b: input.parse().ok()?,
+            });
+        };
+        let a = match &input[..n] {
+            "" | "+" => 1,
+            "-" => -1,
+            a => a.parse().ok()?,
+        };
+        // The sign of `b` may be surrounded by whitespace, as in `2n + 1`.
+        let b: String = input[n + 1..]
+            .chars()
+            .filter(|ch| !ch.is_ascii_whitespace())
+            .collect();
+        let b = match b.chars().next() {
+            None => 0,
+            Some('+' | '-') => b.parse().ok()?,
+            Some(_) => return None,
+        };
+        Some(Nth { a, b })
+    }
+
+    /// Whether the element at `position`, counting from 1, is matched.
+    pub fn matches(&self, position: usize) -> bool {
+        let offset = position as i64 - self.b as i64;
+        match self.a as i64 {
+            0 => offset == 0,
+            a => offset % a == 0 && offset / a >= 0,
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::{iter::Peekable, str::Chars};
+
+use super::{compound::CompoundSelector, nth::Nth, simple::SimpleSelector, Selector};
+
+/// Why a string could not be parsed into a [`Selector`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectorParseError {
+    /// The selector, or a part of it like in `h1,,h2`, is empty.
+    UnexpectedEnd,
+    /// The character is not allowed here.
+    UnexpectedChar(char),
+    /// The pseudo-class is not known, like `:foo`.
+    UnknownPseudoClass(String),
+    /// The feature of CSS selectors is known, but not supported, like
+    /// `:hover`, `::before`, `*`, the `+` and `~` combinators, or attribute
+    /// selectors like `[href^=https]`. It is given as written, without its
+    /// arguments, as in `:hover`, `::before`, `+` or `^=`.
+    Unsupported(String),
+    /// The argument of a pseudo-class like `:nth-of-type()` is not of the
+    /// form `An+B`, `odd` or `even`.
+    InvalidNth(String),
+}
+
+/// The standard pseudo-classes which are not supported.
+const UNSUPPORTED_PSEUDO_CLASSES: [&str; 52] = [
+    "active",
+    "any-link",
+    "autofill",
+    "blank",
+    "checked",
+    "current",
+    "default",
+    "defined",
+    "dir",
+    "disabled",
+    "empty",
+    "enabled",
+    "first",
+    "focus",
+    "focus-visible",
+    "focus-within",
+    "fullscreen",
+    "future",
+    "has",
+    "host",
+    "hover",
+    "in-range",
+    "indeterminate",
+    "invalid",
+    "lang",
+    "left",
+    "link",
+    "local-link",
+    "modal",
+    "not",
+    "nth-col",
+    "nth-last-col",
+    "only-child",
+    "only-of-type",
+    "optional",
+    "out-of-range",
+    "past",
+    "paused",
+    "placeholder-shown",
+    "playing",
+    "popover-open",
+    "read-only",
+    "read-write",
+    "required",
+    "right",
+    "scope",
+    "target",
+    "target-within",
+    "user-invalid",
+    "user-valid",
+    "valid",
+    "visited",
+];
+
+pub fn parse(input: &str) -> Result<Selector, SelectorParseError> {
+    let mut parser = Parser {
+        chars: input.chars().peekable(),
+    };
+    let selector = parser.selector_list()?;
+    match parser.chars.next() {
+        Some(ch) => Err(SelectorParseError::UnexpectedChar(ch)),
+        None => Ok(selector),
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn selector_list(&mut self) -> Result<Selector, SelectorParseError> {
+        let mut compound_selectors = vec![self.complex()?];
+        while self.chars.next_if_eq(&',').is_some() {
+            compound_selectors.push(self.complex()?);
+        }
+        Ok(Selector(compound_selectors))
+    }
+
+    /// Parse compound selectors separated by combinators, like
+    /// `section p`, into the rightmost one, which keeps the others as
+    /// conditions on its ancestors.
+    fn complex(&mut self) -> Result<CompoundSelector, SelectorParseError> {
+        self.skip_whitespace();
+        let mut compound_selector = self.compound()?;
+        loop {
+            let after_whitespace = self.skip_whitespace();
+            match self.chars.peek() {
+                None | Some(',') | Some(')') => return Ok(compound_selector),
+                Some('>') => {
+                    self.chars.next();
+                    self.skip_whitespace();
+                    let mut next = self.compound()?;
+                    next.0
+                        .push(SimpleSelector::ChildOf(Box::new(compound_selector)));
+                    compound_selector = next;
+                }
+                Some(&ch @ ('+' | '~')) => {
+                    return Err(SelectorParseError::Unsupported(ch.to_string()))
+                }
+                Some(_) if after_whitespace => {
+                    let mut next = self.compound()?;
+                    next.0
+                        .push(SimpleSelector::DescendantOf(Box::new(compound_selector)));
+                    compound_selector = next;
+                }
+                Some(&ch) => return Err(SelectorParseError::UnexpectedChar(ch)),
+            }
+        }
+    }
+
+    fn compound(&mut self) -> Result<CompoundSelector, SelectorParseError> {
+        if self.chars.peek() == Some(&'*') {
+            return Err(SelectorParseError::Unsupported("*".to_string()));
+        }
+        let mut simple_selectors = vec![];
+        if self
+            .chars
+            .peek()
+            .is_some_and(|&ch| is_ident_char(ch) || ch == '\\')
+        {
+            simple_selectors.push(SimpleSelector::Tag(self.ident()?));
+        }
+        loop {
+            let simple_selector = match self.chars.peek() {
+                Some('.') => {
+                    self.chars.next();
+                    SimpleSelector::Class(self.ident()?)
+                }
+                Some('#') => {
+                    self.chars.next();
+                    SimpleSelector::Id(self.ident()?)
+                }
+                Some(':') => {
+                    self.chars.next();
+                    self.pseudo_class()?
+                }
+                Some('[') => {
+                    self.chars.next();
+                    self.attribute()?
+                }
+                _ => break,
+            };
+            simple_selectors.push(simple_selector);
+        }
+        if simple_selectors.is_empty() {
+            return Err(self.unexpected());
+        }
+        Ok(CompoundSelector(simple_selectors))
+    }
+
+    fn pseudo_class(&mut self) -> Result<SimpleSelector, SelectorParseError> {
+        if self.chars.next_if_eq(&':').is_some() {
+            let name = self.ident()?;
+            return Err(SelectorParseError::Unsupported(format!("::{}", name)));
+        }
+        let name = self.ident()?;
+        let lowercase = name.to_ascii_lowercase();
+        if UNSUPPORTED_PSEUDO_CLASSES.contains(&lowercase.as_str()) {
+            return Err(SelectorParseError::Unsupported(format!(":{}", name)));
+        }
+        match lowercase.as_str() {
+            "is" | "where" => {
+                self.expect('(')?;
+                let selector = self.selector_list()?;
+                self.expect(')')?;
+                Ok(SimpleSelector::Is(selector))
+            }
+            "root" => Ok(SimpleSelector::Root),
+            "first-child" => Ok(SimpleSelector::NthChild(Nth::FIRST)),
+            "last-child" => Ok(SimpleSelector::NthLastChild(Nth::FIRST)),
+            "nth-child" => Ok(SimpleSelector::NthChild(self.nth()?)),
+            "nth-last-child" => Ok(SimpleSelector::NthLastChild(self.nth()?)),
+            "first-of-type" => Ok(SimpleSelector::NthOfType(Nth::FIRST)),
+            "last-of-type" => Ok(SimpleSelector::NthLastOfType(Nth::FIRST)),
+            "nth-of-type" => Ok(SimpleSelector::NthOfType(self.nth()?)),
+            "nth-last-of-type" => Ok(SimpleSelector::NthLastOfType(self.nth()?)),
+            _ => Err(SelectorParseError::UnknownPseudoClass(name)),
+        }
+    }
+
+    /// Parse an attribute selector after its `[`, like `href]` or
+    /// `type="checkbox" i]`.
+    fn attribute(&mut self) -> Result<SimpleSelector, SelectorParseError> {
+        self.skip_whitespace();
+        let name = self.ident()?;
+        self.skip_whitespace();
+        if let Some(operator) = self.chars.next_if(|ch| "~|^$*".contains(*ch)) {
+            return Err(SelectorParseError::Unsupported(format!("{}=", operator)));
+        }
+        let mut value = None;
+        let mut ignore_case = false;
+        if self.chars.next_if_eq(&'=').is_some() {
+            self.skip_whitespace();
+            value = Some(match self.chars.peek() {
+                Some(&quote @ ('"' | '\'')) => {
+                    self.chars.next();
+                    self.string(quote)?
+                }
+                _ => self.ident()?,
+            });
+            self.skip_whitespace();
+            if let Some(flag) = self.chars.next_if(|ch| matches!(ch, 'i' | 'I' | 's' | 'S')) {
+                ignore_case = flag.eq_ignore_ascii_case(&'i');
+                self.skip_whitespace();
+            }
+        }
+        self.expect(']')?;
+        Ok(SimpleSelector::Attr {
+            name,
+            value,
+            ignore_case,
+        })
+    }
+
+    /// Parse a string after its opening `quote`, where `\` escapes the next
+    /// character.
+    fn string(&mut self, quote: char) -> Result<String, SelectorParseError> {
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                Some(ch) if ch == quote => return Ok(string),
+                Some('\\') => {
+                    string.push(self.chars.next().ok_or(SelectorParseError::UnexpectedEnd)?)
+                }
+                Some(ch) => string.push(ch),
+                None => return Err(SelectorParseError::UnexpectedEnd),
+            }
+        }
+    }
+
+    /// Parse the parenthesized `An+B` argument of a pseudo-class.
+    fn nth(&mut self) -> Result<Nth, SelectorParseError> {
+        self.expect('(')?;
+        let mut argument = String::new();
+        while let Some(ch) = self.chars.next_if(|&ch| ch != ')') {
+            argument.push(ch);
+        }
+        self.expect(')')?;
+        Nth::parse(&argument).ok_or(SelectorParseError::InvalidNth(argument))
+    }
+
+    /// Parse a name, where `\` escapes the next character, as in `log\:warning`.
+    fn ident(&mut self) -> Result<String, SelectorParseError> {
+        let mut ident = String::new();
+        while let Some(&ch) = self.chars.peek() {
+            if ch == '\\' {
+                self.chars.next();
+                ident.push(self.chars.next().ok_or(SelectorParseError::UnexpectedEnd)?);
+            } else if is_ident_char(ch) {
+                self.chars.next();
+                ident.push(ch);
+            } else {
+                break;
+            }
+        }
+        if ident.is_empty() {
+            return Err(self.unexpected());
+        }
+        Ok(ident)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SelectorParseError> {
+        match self.chars.next() {
+            Some(ch) if ch == expected => Ok(()),
+            Some(ch) => Err(SelectorParseError::UnexpectedChar(ch)),
+            None => Err(SelectorParseError::UnexpectedEnd),
+        }
+    }
+
+    /// Skip the whitespace, returning whether there was any.
+    fn skip_whitespace(&mut self) -> bool {
+        let mut skipped = false;
+        while self.chars.next_if(|ch| ch.is_ascii_whitespace()).is_some() {
+            skipped = true;
+        }
+        skipped
+    }
+
+    fn unexpected(&mut self) -> SelectorParseError {
+        match self.chars.peek() {
+            Some(&ch) => SelectorParseError::UnexpectedChar(ch),
+            None => SelectorParseError::UnexpectedEnd,
+        }
+    }
+}
+
+// The other characters can only be in a name when escaped with `\`.
+fn is_ident_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || !ch.is_ascii()
+}
@@ -1,9 +1,106 @@
+use super::{compound::CompoundSelector, nth::Nth, Scope, Selector};
+
 /// A selector with a single component, such as a single
 /// id selector or type selector, that's not used in combination
 /// with or contains any other selector component or combinator.
+///
+/// The combinators are represented here too, as a condition on the
+/// ancestors of the element: `section p` is a `p` which is a
+/// descendant of a `section`.
 #[derive(Debug)]
 pub enum SimpleSelector {
     Class(String),
     Id(String),
     Tag(String),
+    /// `[name]`, or `[name=value]`, where the value is compared ASCII
+    /// case-insensitively with the `i` flag, as in `[type=checkbox i]`.
+    Attr {
+        name: String,
+        value: Option<String>,
+        ignore_case: bool,
+    },
+    /// `:is()` or `:where()`, which match the same elements as the
+    /// selector list inside.
+    Is(Selector),
+    /// `:root`, which matches the outermost elements of the queried
+    /// tree, as they have no parent.
+    Root,
+    /// `:nth-child()`, or `:first-child`, which count the preceding
+    /// sibling elements.
+    NthChild(Nth),
+    /// `:nth-last-child()`, or `:last-child`, which count the following
+    /// sibling elements.
+    NthLastChild(Nth),
+    /// `:nth-of-type()`, or `:first-of-type`, which count the preceding
+    /// siblings with the same tag name.
+    NthOfType(Nth),
+    /// `:nth-last-of-type()`, or `:last-of-type`, which count the
+    /// following siblings with the same tag name.
+    NthLastOfType(Nth),
+    /// The descendant combinator, written as whitespace.
+    DescendantOf(Box<CompoundSelector>),
+    /// The child combinator, written as `>`.
+    ChildOf(Box<CompoundSelector>),
+}
+
+impl SimpleSelector {
+    pub fn matches(&self, scope: &Scope) -> bool {
+        let element = scope.element;
+        match self {
+            SimpleSelector::Class(selector_class) => element.has_class(selector_class),
+            SimpleSelector::Id(selector_id) => element
+                .attrs
+                .iter()
+                .find(|(key, _)| key == "id")
+                .is_some_and(|(_, v)| v == selector_id),
+            SimpleSelector::Tag(tag) => tag.eq_ignore_ascii_case(&element.name),
+            SimpleSelector::Attr {
+                name,
+                value,
+                ignore_case,
+            } => match value {
+                None => element.has_attr(name),
+                Some(value) if *ignore_case => element.has_attr_value_ignore_case(name, value),
+                Some(value) => element.has_attr_value(name, value),
+            },
+            SimpleSelector::Is(selector) => selector.matches_in(scope),
+            SimpleSelector::Root => scope.parent().is_none(),
+            SimpleSelector::NthChild(nth) => nth.matches(scope.siblings().0 + 1),
+            SimpleSelector::NthLastChild(nth) => scope
+                .siblings()
+                .1
+                .is_some_and(|after| nth.matches(after + 1)),
+            SimpleSelector::NthOfType(nth) => nth.matches(scope.siblings_of_type().0 + 1),
+            SimpleSelector::NthLastOfType(nth) => scope
+                .siblings_of_type()
+                .1
+                .is_some_and(|after| nth.matches(after + 1)),
+            SimpleSelector::DescendantOf(compound_selector) => {
+                let mut ancestor = scope.parent();
+                while let Some(scope) = ancestor {
+                    if compound_selector.matches(&scope) {
+                        return true;
+                    }
+                    ancestor = scope.parent();
+                }
+                false
+            }
+            SimpleSelector::ChildOf(compound_selector) => scope
+                .parent()
+                .is_some_and(|parent| compound_selector.matches(&parent)),
+        }
+    }
+
+    pub fn needs_siblings(&self) -> bool {
+        match self {
+            SimpleSelector::NthChild(_)
+            | SimpleSelector::NthLastChild(_)
+            | SimpleSelector::NthOfType(_)
+            | SimpleSelector::NthLastOfType(_) => true,
+            SimpleSelector::Is(selector) => selector.needs_siblings(),
+            SimpleSelector::DescendantOf(compound_selector)
+            | SimpleSelector::ChildOf(compound_selector) => compound_selector.needs_siblings(),
+            _ => false,
+        }
+    }
 }
@@ -28,11 +28,17 @@ impl std::ops::Sub<isize> for SourceLocation {
 }
 
 mod attrs;
+mod stream;
 mod token;
 
-use crate::{data::VOID_TAGS, Element, Node};
+use std::ops::ControlFlow;
+
+use crate::data::{LEGACY_ENTITIES, VOID_TAGS};
+use crate::{Element, Node};
 use token::Token;
 
+pub use stream::select_streaming;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct HTMLParseError {
     pub source_location: SourceLocation,
@@ -53,13 +59,114 @@ impl InnerHTMLParseError {
     }
 }
 
-fn html_to_stack(html: &str) -> Result<Vec<(Token, SourceLocation)>, HTMLParseError> {
-    let mut chars_stack = Vec::<char>::new();
+/// Options to control how the html is parsed.
+///
+/// ```
+/// use html_editor::{parse_with_options, ParseOptions};
+/// use html_editor::operation::*;
+///
+/// let options = ParseOptions {
+///     preserve_tag_case: false,
+///     ..Default::default()
+/// };
+/// let nodes = parse_with_options("<DIV>Hello</DIV>", &options).unwrap();
+/// assert_eq!(nodes.html(), "<div>Hello</div>");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Whether to keep the tag names as they are written, like `DIV` in
+    /// `<DIV>`, instead of lowercasing them. Defaults to `true`.
+    ///
+    /// Either way, the tag names are matched case-insensitively, both
+    /// by the parser and by selectors.
+    pub preserve_tag_case: bool,
+    /// Whether to keep the tags which can't be parsed, like `<?php ... ?>`
+    /// or `< >`, as they are written in a [`Node::RawHTML`], instead of
+    /// returning an error. Defaults to `false`.
+    ///
+    /// Those tags are then written back as they were. The rest of the html
+    /// is parsed as usual, so
+    /// [`try_parse_with_options()`](try_parse_with_options) still drops the
+    /// end tags it can't pair.
+    ///
+    /// ```
+    /// use html_editor::{parse, parse_with_options, Node, ParseOptions};
+    /// use html_editor::operation::*;
+    ///
+    /// let html = "<p><?php echo $name; ?></p>";
+    /// assert!(parse(html).is_err());
+    ///
+    /// let options = ParseOptions {
+    ///     raw_on_error: true,
+    ///     ..Default::default()
+    /// };
+    /// let nodes = parse_with_options(html, &options).unwrap();
+    /// assert!(matches!(&nodes[0].children().unwrap()[0], Node::RawHTML(php) if php == "<?php echo $name; ?>"));
+    /// assert_eq!(nodes.html(), html);
+    /// ```
+    pub raw_on_error: bool,
+    /// Whether the document is XML, like an `.svg` file, instead of HTML.
+    /// Defaults to `false`.
+    ///
+    /// It changes how `<![CDATA[...]]>` is read: in HTML, it is a comment,
+    /// except in foreign content, that is in `<svg>` and `<math>`, where it
+    /// is a [`Node::CData`], as it always is in XML. A section containing
+    /// `-->` is a [`Node::CData`] in HTML too, as it would end the comment.
+    ///
+    /// ```
+    /// use html_editor::{parse, parse_with_options, Node, ParseOptions};
+    ///
+    /// let html = "<![CDATA[x < y]]>";
+    /// assert!(matches!(&parse(html).unwrap()[0], Node::Comment(comment) if comment == "[CDATA[x < y]]"));
+    ///
+    /// let options = ParseOptions {
+    ///     xml: true,
+    ///     ..Default::default()
+    /// };
+    /// let nodes = parse_with_options(html, &options).unwrap();
+    /// assert!(matches!(&nodes[0], Node::CData(text) if text == "x < y"));
+    /// ```
+    pub xml: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            preserve_tag_case: true,
+            raw_on_error: false,
+            xml: false,
+        }
+    }
+}
+
+fn html_to_stack(
+    html: &str,
+    options: &ParseOptions,
+) -> Result<Vec<(Token, SourceLocation)>, HTMLParseError> {
     let mut token_stack = Vec::<(Token, SourceLocation)>::new();
+    tokenize(html, options, |token, location| {
+        token_stack.push((token, location));
+        ControlFlow::Continue(())
+    })?;
+    Ok(token_stack)
+}
+
+/// Split the html into tokens, passing each one to `emit` as soon as it
+/// is read, until it returns [`ControlFlow::Break`].
+fn tokenize(
+    html: &str,
+    options: &ParseOptions,
+    mut emit: impl FnMut(Token, SourceLocation) -> ControlFlow<()>,
+) -> Result<(), HTMLParseError> {
+    let mut chars_stack = Vec::<char>::new();
     let mut in_quotes: Option<char> = None;
     // More precisely: is in angle brackets
     let mut in_brackets = false;
     let mut in_comment = false;
+    let mut in_cdata = false;
+    // How many `<svg>` and `<math>` are open, in which CDATA sections
+    // are read as such
+    let mut foreign_depth = 0usize;
     let mut in_script = false;
     let mut in_style = false;
     for (i, ch) in html.chars().enumerate() {
@@ -79,15 +186,40 @@ fn html_to_stack(html: &str) -> Result<Vec<(Token, SourceLocation)>, HTMLParseEr
         } else if in_comment {
             chars_stack.push(ch);
 
-            if ends_with(&chars_stack, &['-', '-', '>']) {
+            // The `-->` can't overlap the `<!--`, as in `<!-->`
+            if chars_stack.len() >= 7 && ends_with(&chars_stack, &['-', '-', '>']) {
                 let comment_len = chars_stack.len();
                 let comment = String::from_iter(chars_stack);
                 chars_stack = Vec::new();
                 let start_loc = next_loc - comment_len as isize;
-                token_stack.push((Token::from_comment(comment), start_loc));
+                if emit(Token::from_comment(comment), start_loc).is_break() {
+                    return Ok(());
+                }
                 in_comment = false;
                 in_brackets = false;
             }
+        } else if in_cdata {
+            chars_stack.push(ch);
+
+            if ends_with(&chars_stack, &[']', ']', '>']) {
+                let cdata_len = chars_stack.len();
+                let text = String::from_iter(&chars_stack[9..cdata_len - 3]);
+                chars_stack = Vec::new();
+                let start_loc = next_loc - cdata_len as isize;
+                // In HTML, it is a bogus comment, which ends at the first
+                // `>`, but the section is kept whole. It is left as is when
+                // it contains `-->`, as the comment couldn't be written back.
+                let token = if options.xml || foreign_depth > 0 || text.contains("-->") {
+                    Token::CData(text)
+                } else {
+                    Token::Comment(format!("[CDATA[{}]]", text))
+                };
+                if emit(token, start_loc).is_break() {
+                    return Ok(());
+                }
+                in_cdata = false;
+                in_brackets = false;
+            }
         } else if in_script {
             chars_stack.push(ch);
             let len = chars_stack.len();
@@ -95,11 +227,16 @@ fn html_to_stack(html: &str) -> Result<Vec<(Token, SourceLocation)>, HTMLParseEr
             if ends_with(&chars_stack, &['<', '/', 's', 'c', 'r', 'i', 'p', 't', '>']) {
                 let script_len = chars_stack.len() - 9;
                 let script = String::from_iter(chars_stack[..len - 9].to_vec());
+                let end_tag = Token::End(String::from_iter(&chars_stack[len - 7..len - 1]));
                 chars_stack = Vec::new();
                 let script_start_loc = next_loc - 9 - script_len as isize;
                 let script_end_tag_start_loc = next_loc - 9;
-                token_stack.push((Token::Text(script), script_start_loc));
-                token_stack.push((Token::End("script".to_string()), script_end_tag_start_loc));
+                if emit(Token::Text(script), script_start_loc).is_break() {
+                    return Ok(());
+                }
+                if emit(end_tag.with_tag_case(options), script_end_tag_start_loc).is_break() {
+                    return Ok(());
+                }
                 in_script = false;
             }
         } else if in_style {
@@ -109,11 +246,16 @@ fn html_to_stack(html: &str) -> Result<Vec<(Token, SourceLocation)>, HTMLParseEr
             if ends_with(&chars_stack, &['<', '/', 's', 't', 'y', 'l', 'e', '>']) {
                 let style_len = chars_stack.len() - 8;
                 let style = String::from_iter(chars_stack[..len - 8].to_vec());
+                let end_tag = Token::End(String::from_iter(&chars_stack[len - 6..len - 1]));
                 chars_stack = Vec::new();
                 let style_start_loc = next_loc - 8 - style_len as isize;
                 let style_end_tag_start_loc = next_loc - 8;
-                token_stack.push((Token::Text(style), style_start_loc));
-                token_stack.push((Token::End("style".to_string()), style_end_tag_start_loc));
+                if emit(Token::Text(style), style_start_loc).is_break() {
+                    return Ok(());
+                }
+                if emit(end_tag.with_tag_case(options), style_end_tag_start_loc).is_break() {
+                    return Ok(());
+                }
                 in_style = false;
             }
         } else {
@@ -127,9 +269,12 @@ fn html_to_stack(html: &str) -> Result<Vec<(Token, SourceLocation)>, HTMLParseEr
                         let txt_len = chars_stack.len();
                         let txt_text = String::from_iter(chars_stack);
                         chars_stack = Vec::new();
-                        let text_start_loc = next_loc - txt_len as isize;
+                        // The text ends right before the `<` at `loc`
+                        let text_start_loc = loc - txt_len as isize;
                         // Push the text we just got to the token stack.
-                        token_stack.push((Token::from_raw_text(txt_text), text_start_loc));
+                        if emit(Token::from_raw_text(txt_text), text_start_loc).is_break() {
+                            return Ok(());
+                        }
                     }
                     chars_stack.push(ch);
                 }
@@ -143,16 +288,29 @@ fn html_to_stack(html: &str) -> Result<Vec<(Token, SourceLocation)>, HTMLParseEr
                     chars_stack = Vec::new();
                     // Push the tag with the text we just got to the token stack.
                     let start_loc = next_loc - tag_text_len as isize;
-                    let tag = Token::from(tag_text.clone()).map_err(|e| e.with_location(start_loc))?;
-                    token_stack.push((tag.clone(), start_loc));
+                    let tag = match Token::from(tag_text.clone()) {
+                        Ok(tag) => tag.with_tag_case(options),
+                        Err(_) if options.raw_on_error => Token::Raw(tag_text),
+                        Err(e) => return Err(e.with_location(start_loc)),
+                    };
+                    if emit(tag.clone(), start_loc).is_break() {
+                        return Ok(());
+                    }
                     // Handle special tags
-                    if let Token::Start(tag_name, _) = tag {
-                        let tag_name = tag_name.as_str();
-                        match tag_name {
-                            "script" => in_script = true,
-                            "style" => in_style = true,
-                            _ => {}
+                    match tag {
+                        Token::Start(tag_name, _) => {
+                            if tag_name.eq_ignore_ascii_case("script") {
+                                in_script = true;
+                            } else if tag_name.eq_ignore_ascii_case("style") {
+                                in_style = true;
+                            } else if is_foreign_root(&tag_name) {
+                                foreign_depth += 1;
+                            }
+                        }
+                        Token::End(tag_name) if is_foreign_root(&tag_name) => {
+                            foreign_depth = foreign_depth.saturating_sub(1);
                         }
+                        _ => {}
                     }
                 }
                 '-' => {
@@ -161,6 +319,12 @@ fn html_to_stack(html: &str) -> Result<Vec<(Token, SourceLocation)>, HTMLParseEr
                         in_comment = true;
                     }
                 }
+                '[' => {
+                    chars_stack.push(ch);
+                    if chars_stack == ['<', '!', '[', 'C', 'D', 'A', 'T', 'A', '['] {
+                        in_cdata = true;
+                    }
+                }
                 _ => {
                     if in_brackets {
                         match ch {
@@ -178,9 +342,11 @@ fn html_to_stack(html: &str) -> Result<Vec<(Token, SourceLocation)>, HTMLParseEr
         let text_len = chars_stack.len();
         let text = String::from_iter(chars_stack);
         let text_start_loc = SourceLocation(html.chars().count() - text_len);
-        token_stack.push((Token::from_raw_text(text), text_start_loc));
+        if emit(Token::from_raw_text(text), text_start_loc).is_break() {
+            return Ok(());
+        }
     }
-    Ok(token_stack)
+    Ok(())
 }
 
 fn stack_to_dom(token_stack: Vec<(Token, SourceLocation)>) -> Result<Vec<Node>, HTMLParseError> {
@@ -191,7 +357,7 @@ fn stack_to_dom(token_stack: Vec<(Token, SourceLocation)>) -> Result<Vec<Node>,
         let location = *location;
         match token {
             Token::Start(tag, attrs) => {
-                let is_void_tag = VOID_TAGS.contains(&tag.as_str());
+                let is_void_tag = is_void_tag(tag);
                 if start_tags_stack.is_empty() {
                     if is_void_tag {
                         nodes.push(
@@ -199,6 +365,7 @@ fn stack_to_dom(token_stack: Vec<(Token, SourceLocation)>) -> Result<Vec<Node>,
                                 name: tag.clone(),
                                 attrs: attrs.clone(),
                                 children: Vec::new(),
+                                dirty: false,
                             }
                             .into_node(),
                         );
@@ -223,7 +390,7 @@ fn stack_to_dom(token_stack: Vec<(Token, SourceLocation)>) -> Result<Vec<Node>,
                             .with_location(location)
                     ),
                 };
-                if tag != &start_tag.name {
+                if !tag.eq_ignore_ascii_case(&start_tag.name) {
                     return Err(
                         InnerHTMLParseError::MismatchedTags { start_tag: start_tag.name, start_location, end_tag: tag.to_string() }
                             .with_location(location)
@@ -235,6 +402,7 @@ fn stack_to_dom(token_stack: Vec<(Token, SourceLocation)>) -> Result<Vec<Node>,
                             name: start_tag.name,
                             attrs: start_tag.attrs,
                             children: stack_to_dom(token_stack[start_tag_index + 1..i].to_vec())?,
+                            dirty: false,
                         }
                         .into_node(),
                     )
@@ -269,7 +437,7 @@ fn try_stack_to_dom(token_stack: Vec<(Token, SourceLocation)>) -> Vec<Node> {
         let _location = *location;
         match token {
             Token::Start(tag, attrs) => {
-                let is_void_tag = VOID_TAGS.contains(&tag.as_str());
+                let is_void_tag = is_void_tag(tag);
                 if start_tags_stack.is_empty() {
                     if is_void_tag {
                         nodes.push(
@@ -277,6 +445,7 @@ fn try_stack_to_dom(token_stack: Vec<(Token, SourceLocation)>) -> Vec<Node> {
                                 name: tag.clone(),
                                 attrs: attrs.clone(),
                                 children: Vec::new(),
+                                dirty: false,
                             }
                             .into_node(),
                         );
@@ -301,7 +470,7 @@ fn try_stack_to_dom(token_stack: Vec<(Token, SourceLocation)>) -> Vec<Node> {
                     None => continue,
                 };
 
-                if tag != &start_tag.name {
+                if !tag.eq_ignore_ascii_case(&start_tag.name) {
                     // The tags do not match, so let's put it back to
                     // pretend we never come here and then continue
                     // the next loop.
@@ -317,6 +486,7 @@ fn try_stack_to_dom(token_stack: Vec<(Token, SourceLocation)>) -> Vec<Node> {
                             children: try_stack_to_dom(
                                 token_stack[start_tag_index + 1..i].to_vec(),
                             ),
+                            dirty: false,
                         }
                         .into_node(),
                     )
@@ -330,17 +500,18 @@ fn try_stack_to_dom(token_stack: Vec<(Token, SourceLocation)>) -> Vec<Node> {
         }
     }
 
-    while let Some(token) = start_tags_stack.pop() {
-        let node = match token {
-            Token::Start(name, attrs) => Element {
+    // The outermost element which is not closed gets the rest of the
+    // tokens as children, after the nodes before it.
+    if let Some(Token::Start(name, attrs)) = start_tags_stack.into_iter().next() {
+        nodes.push(
+            Element {
                 name,
                 attrs,
                 children: try_stack_to_dom(token_stack[start_tag_index + 1..].to_vec()),
+                dirty: false,
             }
             .into_node(),
-            _ => unreachable!(),
-        };
-        nodes = vec![node];
+        );
     }
     nodes
 }
@@ -376,7 +547,7 @@ fn try_stack_to_dom(token_stack: Vec<(Token, SourceLocation)>) -> Vec<Node> {
 ///     },
 /// ]
 /// [
-///     Doctype(Html),
+///     Doctype(Spelled("<!doctype html>")),
 ///     Element {
 ///         name: "html",
 ///         attrs: {},
@@ -396,11 +567,125 @@ fn try_stack_to_dom(token_stack: Vec<(Token, SourceLocation)>) -> Vec<Node> {
 /// ]
 /// ```
 pub fn parse(html: &str) -> Result<Vec<Node>, HTMLParseError> {
-    let stack = html_to_stack(html)?;
+    parse_with_options(html, &ParseOptions::default())
+}
+
+/// Same as [`parse()`](parse), but with the given [`ParseOptions`].
+pub fn parse_with_options(html: &str, options: &ParseOptions) -> Result<Vec<Node>, HTMLParseError> {
+    let stack = html_to_stack(html, options)?;
 
     stack_to_dom(stack)
 }
 
+/// The nodes parsed by [`parse_with_warnings()`](parse_with_warnings),
+/// along with what is unusual about their structure.
+#[derive(Debug, Clone)]
+pub struct ParseResult {
+    pub nodes: Vec<Node>,
+    pub warnings: Vec<ParseWarning>,
+}
+
+/// Something unusual about a document that is not an error.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseWarning {
+    /// There is no element at the top level.
+    NoRootElement,
+    /// There are `count` elements at the top level, instead of a single
+    /// root element.
+    MultipleRootElements { count: usize },
+    /// There is text other than whitespace at the top level.
+    TextOutsideRootElement,
+}
+
+impl ParseResult {
+    fn new(nodes: Vec<Node>) -> Self {
+        let mut warnings = Vec::new();
+        match nodes.iter().filter(|node| node.is_element()).count() {
+            0 => warnings.push(ParseWarning::NoRootElement),
+            1 => {}
+            count => warnings.push(ParseWarning::MultipleRootElements { count }),
+        }
+        if nodes
+            .iter()
+            .any(|node| matches!(node, Node::Text(..)) && !node.is_whitespace_text())
+        {
+            warnings.push(ParseWarning::TextOutsideRootElement);
+        }
+        ParseResult { nodes, warnings }
+    }
+
+    /// Check if the nodes are a fragment rather than a full document,
+    /// which means they are not a single root element.
+    pub fn is_fragment(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
+    /// Make sure there is a single root element, by moving all the nodes
+    /// into a new `tag` element if the nodes are a fragment.
+    ///
+    /// Doctypes are kept at the top level, along with the comments and
+    /// whitespace before them.
+    ///
+    /// ```
+    /// use html_editor::parse_with_warnings;
+    /// use html_editor::operation::*;
+    ///
+    /// let mut result = parse_with_warnings("<p>Hello</p><p>World</p>").unwrap();
+    /// assert!(result.is_fragment());
+    ///
+    /// result.wrap_in_root("body");
+    /// assert!(!result.is_fragment());
+    /// assert_eq!(result.nodes.html(), "<body><p>Hello</p><p>World</p></body>");
+    /// ```
+    pub fn wrap_in_root(&mut self, tag: &str) -> &mut Self {
+        if !self.is_fragment() {
+            return self;
+        }
+        let mut nodes = std::mem::take(&mut self.nodes);
+        let prolog_len = nodes
+            .iter()
+            .take_while(|node| match node {
+                Node::Doctype(..) | Node::Comment(..) => true,
+                Node::Text(..) => node.is_whitespace_text(),
+                _ => false,
+            })
+            .enumerate()
+            .filter(|(_, node)| matches!(node, Node::Doctype(..)))
+            .last()
+            .map_or(0, |(index, _)| index + 1);
+        let (doctypes, children): (Vec<Node>, Vec<Node>) = nodes
+            .split_off(prolog_len)
+            .into_iter()
+            .partition(|node| matches!(node, Node::Doctype(..)));
+        nodes.extend(doctypes);
+        // The root is new, but the layout of its children is still the parsed one
+        nodes.push(Node::Element(Element {
+            name: tag.to_string(),
+            attrs: Vec::new(),
+            children,
+            dirty: false,
+        }));
+        *self = ParseResult::new(nodes);
+        self
+    }
+}
+
+/// Same as [`parse()`](parse), but also reports whether the nodes
+/// are a single root element or a fragment.
+///
+/// ```
+/// use html_editor::{parse_with_warnings, ParseWarning};
+///
+/// let result = parse_with_warnings("<!DOCTYPE html><html></html>").unwrap();
+/// assert!(result.warnings.is_empty());
+///
+/// let result = parse_with_warnings("<li>1</li><li>2</li>").unwrap();
+/// assert_eq!(result.warnings, vec![ParseWarning::MultipleRootElements { count: 2 }]);
+/// ```
+pub fn parse_with_warnings(html: &str) -> Result<ParseResult, HTMLParseError> {
+    parse(html).map(ParseResult::new)
+}
+
 /// Alternative for [`parse()`](parse) with fault tolerance
 /// feature.
 ///
@@ -418,17 +703,234 @@ pub fn parse(html: &str) -> Result<Vec<Node>, HTMLParseError> {
 /// assert_eq!(result, "<div><a>Ipsum</a></div>");
 /// ```
 pub fn try_parse(html: &str) -> Vec<Node> {
-    let stack = html_to_stack(html).unwrap_or_default();
+    try_parse_with_options(html, &ParseOptions::default())
+}
+
+/// Same as [`try_parse()`](try_parse), but with the given [`ParseOptions`].
+pub fn try_parse_with_options(html: &str, options: &ParseOptions) -> Vec<Node> {
+    let stack = html_to_stack(html, options).unwrap_or_default();
 
     try_stack_to_dom(stack)
 }
 
+/// Indent the html, putting each node on its own line like
+/// [`Format::Pretty`](crate::operation::Format::Pretty) does.
+///
+/// Unlike `Format::Pretty`, which writes the parsed DOM, this works on the
+/// source itself, so the tags, attribute values and text are kept byte for
+/// byte, including their entities. Only the whitespace around the text is
+/// trimmed, and the text which is only whitespace is dropped. The content of
+/// `<pre>`, `<textarea>`, `<script>` and `<style>` is kept as is.
+///
+/// ```
+/// use html_editor::prettify;
+///
+/// let html = "<ul><li>&nbsp;1</li>  <li class='x'>a <b>b</b></li></ul>";
+/// assert_eq!(
+///     prettify(html, "  ").unwrap(),
+///     "<ul>\n  <li>&nbsp;1</li>\n  <li class='x'>\n    a\n    <b>b</b>\n  </li>\n</ul>"
+/// );
+/// ```
+///
+/// The html has to be valid, just like for [`parse()`](parse).
+pub fn prettify(html: &str, indent: &str) -> Result<String, HTMLParseError> {
+    let stack = html_to_stack(html, &ParseOptions::default())?;
+    stack_to_dom(stack.clone())?;
+
+    // The locations count chars, so they are mapped to byte offsets
+    let offsets: Vec<usize> = html
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .chain(std::iter::once(html.len()))
+        .collect();
+    let ends: Vec<usize> = stack
+        .iter()
+        .skip(1)
+        .map(|(_, location)| location.0)
+        .chain(std::iter::once(offsets.len() - 1))
+        .collect();
+    let tokens: Vec<(Token, &str)> = stack
+        .into_iter()
+        .zip(ends)
+        .map(|((token, start), end)| (token, &html[offsets[start.0]..offsets[end]]))
+        .collect();
+
+    let mut pretty = String::new();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let (token, raw) = &tokens[i];
+        i += 1;
+        let raw = match token {
+            Token::Text(_) if raw.trim_matches(is_whitespace).is_empty() => continue,
+            Token::Text(_) => raw.trim_matches(is_whitespace),
+            Token::End(_) => {
+                depth -= 1;
+                raw
+            }
+            _ => raw,
+        };
+        if !pretty.is_empty() {
+            pretty.push('\n');
+        }
+        pretty.push_str(&indent.repeat(depth));
+        pretty.push_str(raw);
+
+        let Token::Start(name, _) = token else {
+            continue;
+        };
+        if is_void_tag(name) {
+            continue;
+        }
+        if ["pre", "textarea", "script", "style"]
+            .iter()
+            .any(|tag| tag.eq_ignore_ascii_case(name))
+        {
+            let end = matching_end(&tokens, i);
+            for (_, raw) in &tokens[i..=end] {
+                pretty.push_str(raw);
+            }
+            i = end + 1;
+            continue;
+        }
+        // An element with no children, or a single text, stays on one line
+        match &tokens[i..] {
+            [(Token::End(_), end), ..] => {
+                pretty.push_str(end);
+                i += 1;
+            }
+            [(Token::Text(_), text), (Token::End(_), end), ..] => {
+                pretty.push_str(text.trim_matches(is_whitespace));
+                pretty.push_str(end);
+                i += 2;
+            }
+            _ => depth += 1,
+        }
+    }
+    Ok(pretty)
+}
+
+// The index of the end token closing the element whose content starts at `start`.
+fn matching_end(tokens: &[(Token, &str)], start: usize) -> usize {
+    let mut depth = 0;
+    for (i, (token, _)) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::Start(name, _) if !is_void_tag(name) => depth += 1,
+            Token::End(_) if depth == 0 => return i,
+            Token::End(_) => depth -= 1,
+            _ => {}
+        }
+    }
+    unreachable!("the tags are checked to be paired before")
+}
+
+fn is_whitespace(ch: char) -> bool {
+    ch.is_ascii_whitespace()
+}
+
+/// Decode the entities in `text`.
+///
+/// Most text does not contain any entities at all, in which case the
+/// `String` is returned as is instead of being copied by `html_escape`.
+fn decode_entities(text: String) -> String {
+    if text.contains('&') {
+        decode_references(&text, false)
+    } else {
+        text
+    }
+}
+
+fn decode_attribute_entities(text: String) -> String {
+    if text.contains('&') {
+        decode_references(&text, true)
+    } else {
+        text
+    }
+}
+
+// Browsers also accept numeric and some legacy named character references
+// without the trailing `;`, e.g. `&copy 2020`, see "named character reference
+// state" in the HTML spec. Each reference is decoded on its own, as
+// `html_escape` skips the reference which follows an unknown one.
+fn decode_references(text: &str, in_attribute: bool) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('&') {
+        decoded.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+        match reference_at_start(rest, in_attribute) {
+            Some((len, character)) => {
+                decoded.push_str(&character);
+                rest = &rest[len..];
+            }
+            None => decoded.push('&'),
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+// Returns the length and the decoded value of the reference at the start of
+// `text`, which is just after the `&`.
+fn reference_at_start(text: &str, in_attribute: bool) -> Option<(usize, String)> {
+    if let Some(numeric) = text.strip_prefix('#') {
+        let (prefix_len, digits) = match numeric.strip_prefix(['x', 'X']) {
+            Some(hex) => (2, hex.bytes().take_while(u8::is_ascii_hexdigit).count()),
+            None => (1, numeric.bytes().take_while(u8::is_ascii_digit).count()),
+        };
+        if digits == 0 {
+            return None;
+        }
+        let len = prefix_len + digits;
+        let len = len + usize::from(text[len..].starts_with(';'));
+        return Some((len, decode_reference(&text[..len])?));
+    }
+
+    let name_len = text.bytes().take_while(u8::is_ascii_alphanumeric).count();
+    if text[name_len..].starts_with(';') {
+        if let Some(character) = decode_reference(&text[..=name_len]) {
+            return Some((name_len + 1, character));
+        }
+    }
+    let len = LEGACY_ENTITIES
+        .iter()
+        .filter(|entity| text[..name_len].starts_with(*entity))
+        .map(|entity| entity.len())
+        .max()?;
+    // For historical reasons, `&copy=` and `&copyx` are kept as is in attributes.
+    let next = text[len..].chars().next();
+    if in_attribute && matches!(next, Some(ch) if ch == '=' || ch.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some((len, decode_reference(&text[..len])?))
+}
+
+fn decode_reference(reference: &str) -> Option<String> {
+    let reference = format!("&{};", reference.trim_end_matches(';'));
+    let decoded = html_escape::decode_html_entities(&reference);
+    (decoded != reference).then(|| decoded.into_owned())
+}
+
+fn is_foreign_root(tag: &str) -> bool {
+    tag.eq_ignore_ascii_case("svg") || tag.eq_ignore_ascii_case("math")
+}
+
+pub(crate) fn is_void_tag(tag: &str) -> bool {
+    VOID_TAGS
+        .iter()
+        .any(|void_tag| void_tag.eq_ignore_ascii_case(tag))
+}
+
 // Use `&[char]` instead of `&str` to improve performance.
-fn ends_with(chars: &Vec<char>, end: &[char]) -> bool {
+// The comparison is case-insensitive, so that `</SCRIPT>` ends a `<script>` too.
+fn ends_with(chars: &[char], end: &[char]) -> bool {
     let chars_len = chars.len();
     let end_len = end.len();
+    if chars_len < end_len {
+        return false;
+    }
     for i in 1..(end_len + 1) {
-        if chars[chars_len - i] != end[end.len() - i] {
+        if !chars[chars_len - i].eq_ignore_ascii_case(&end[end.len() - i]) {
             return false;
         }
     }
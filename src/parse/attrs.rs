@@ -1,3 +1,5 @@
+use super::decode_attribute_entities;
+
 // Let's take `<img src="example.png" alt=image>` for example.
 enum AttrPos {
     /// Includes `src`, `alt`
@@ -6,12 +8,14 @@ enum AttrPos {
     Equal,
     /// Includes `example.png`, `image`
     Value(Option<char>),
-    /// Includes ` `
+    /// Includes ` `, and the other ASCII whitespace like line breaks
     Space,
 }
 
 /// Valid `attr_str` like: `src="example.png" alt=example disabled`
-pub fn parse(attr_str: String) -> Vec<(String, String)> {
+///
+/// Returns the reason when it is invalid, like `a=` or `a="b`.
+pub fn parse(attr_str: String) -> Result<Vec<(String, String)>, &'static str> {
     let mut chars_stack: Vec<char> = Vec::new();
     let mut key_stack: Vec<String> = Vec::new();
     let mut value_stack: Vec<String> = Vec::new();
@@ -25,7 +29,7 @@ pub fn parse(attr_str: String) -> Vec<(String, String)> {
                     chars_stack = Vec::new();
                     key_stack.push(key)
                 }
-                ' ' => {
+                _ if ch.is_ascii_whitespace() => {
                     attr_pos = AttrPos::Space;
                     let key = String::from_iter(chars_stack);
                     chars_stack = Vec::new();
@@ -44,7 +48,7 @@ pub fn parse(attr_str: String) -> Vec<(String, String)> {
             },
             AttrPos::Value(delimiter) => match delimiter {
                 None => {
-                    if ch == ' ' {
+                    if ch.is_ascii_whitespace() {
                         attr_pos = AttrPos::Space;
                         let value = String::from_iter(chars_stack);
                         chars_stack = Vec::new();
@@ -70,7 +74,7 @@ pub fn parse(attr_str: String) -> Vec<(String, String)> {
                             attr_pos = AttrPos::Space;
                             let value = String::from_iter(chars_stack);
                             chars_stack = Vec::new();
-                            value_stack.push(html_escape::decode_html_entities(&value).into_owned())
+                            value_stack.push(decode_attribute_entities(value))
                         }
                     } else {
                         chars_stack.push(ch)
@@ -78,7 +82,7 @@ pub fn parse(attr_str: String) -> Vec<(String, String)> {
                 }
             },
             AttrPos::Space => {
-                if ch != ' ' {
+                if !ch.is_ascii_whitespace() {
                     attr_pos = AttrPos::Key;
                     chars_stack.push(ch);
                 }
@@ -86,9 +90,6 @@ pub fn parse(attr_str: String) -> Vec<(String, String)> {
         }
     }
 
-    let err_info = format!("cannot parse the attributes: {}", attr_str);
-    let err_info = err_info.as_str();
-
     if !chars_stack.is_empty() {
         let str = String::from_iter(chars_stack);
         match attr_pos {
@@ -100,7 +101,7 @@ pub fn parse(attr_str: String) -> Vec<(String, String)> {
                 if delimiter.is_none() {
                     value_stack.push(str);
                 } else {
-                    panic!("{}", err_info)
+                    return Err("Attribute value is not closed by a quote");
                 }
             }
             _ => {}
@@ -108,19 +109,9 @@ pub fn parse(attr_str: String) -> Vec<(String, String)> {
     }
 
     if key_stack.len() != value_stack.len() {
-        panic!(
-            "{}\nkey:\t{:?}\nvalue:\t{:?}",
-            err_info, key_stack, value_stack
-        )
+        return Err("Attribute has no value after \"=\"");
     }
 
-    let mut attrs = Vec::new();
-    let len = key_stack.len();
-    for _ in 0..len {
-        attrs.push((
-            key_stack.pop().expect(err_info),
-            value_stack.pop().expect(err_info),
-        ));
-    }
-    attrs
+    // Keep the source order, as the first of duplicate attributes wins
+    Ok(key_stack.into_iter().zip(value_stack).collect())
 }
@@ -0,0 +1,203 @@
+use std::ops::ControlFlow;
+
+use super::token::Token;
+use super::{
+    is_void_tag, stack_to_dom, tokenize, HTMLParseError, InnerHTMLParseError, ParseOptions,
+    SourceLocation,
+};
+use crate::operation::{Scope, Selector, Siblings};
+use crate::{Element, Node};
+
+/// An element whose end tag has not been read yet.
+struct OpenElement {
+    /// A copy of the element without its children.
+    element: Element,
+    location: SourceLocation,
+    /// The index of its start tag in the buffered tokens, if it matches.
+    matched: Option<usize>,
+    /// Copies of the children read so far, when the selector needs them.
+    children: Vec<Element>,
+}
+
+/// Call `f` with each element of `html` matching the `selector`, while the
+/// html is read, without building the DOM of the whole document, until `f`
+/// returns [`ControlFlow::Break`].
+///
+/// Only the elements which are open, that is whose start tag is read but
+/// not their end tag yet, are kept to match the selector. The tokens of a
+/// matching element are kept until its end tag, to build the element with
+/// its children, so `f` is called once the element ends. As a result, an
+/// element inside another matching element comes first.
+///
+/// The selector is matched as it would be on the DOM, except for what
+/// depends on the following siblings, which are not read yet: type, class,
+/// id and attribute selectors, the descendant and child combinators,
+/// `:root`, `:is()`, `:where()`, `:first-child`, `:nth-child()`,
+/// `:first-of-type` and `:nth-of-type()` are supported, but the `last` ones
+/// like `:last-child` or `:nth-last-of-type()` never match.
+///
+/// Like [`parse`](crate::parse), it returns an error if the html is invalid,
+/// unless the error comes after `f` returns [`ControlFlow::Break`].
+///
+/// ```
+/// use std::ops::ControlFlow;
+/// use html_editor::select_streaming;
+/// use html_editor::operation::*;
+///
+/// let html = r#"<ul><li><a href="/a">A</a></li><li><a href="/b">B</a></li></ul><p>and so on"#;
+/// let selector = Selector::from("li > a");
+///
+/// let mut links = Vec::new();
+/// select_streaming(html, &selector, |a| {
+///     links.push(a.get_attr("href").unwrap().to_string());
+///     ControlFlow::Break(())
+/// })
+/// .unwrap();
+///
+/// // The rest of the html, which has an unclosed `<p>`, is not even read
+/// assert_eq!(links, vec!["/a"]);
+/// ```
+pub fn select_streaming(
+    html: &str,
+    selector: &Selector,
+    mut f: impl FnMut(Element) -> ControlFlow<()>,
+) -> Result<(), HTMLParseError> {
+    let needs_siblings = selector.needs_siblings();
+    let mut open: Vec<OpenElement> = Vec::new();
+    // Copies of the top-level elements, when the selector needs them.
+    let mut top_level: Vec<Element> = Vec::new();
+    // The tokens from the start tag of the outermost matching open element.
+    let mut tokens: Vec<(Token, SourceLocation)> = Vec::new();
+    let mut error = None;
+    let mut stopped = false;
+
+    tokenize(html, &ParseOptions::default(), |token, location| {
+        let buffering = !tokens.is_empty();
+        match &token {
+            Token::Start(name, attrs) | Token::Closing(name, attrs) => {
+                let element = Element {
+                    name: name.clone(),
+                    attrs: attrs.clone(),
+                    children: Vec::new(),
+                    dirty: false,
+                };
+                if needs_siblings {
+                    match open.last_mut() {
+                        Some(parent) => parent.children.push(element.clone()),
+                        None => top_level.push(element.clone()),
+                    }
+                }
+                let is_match = is_matching(selector, &element, &open, &top_level, needs_siblings);
+                let is_void = matches!(token, Token::Closing(..)) || is_void_tag(name);
+                if is_void {
+                    if buffering {
+                        tokens.push((token, location));
+                    }
+                    if is_match && f(element).is_break() {
+                        stopped = true;
+                        return ControlFlow::Break(());
+                    }
+                } else {
+                    if is_match || buffering {
+                        tokens.push((token, location));
+                    }
+                    open.push(OpenElement {
+                        element,
+                        location,
+                        matched: is_match.then(|| tokens.len() - 1),
+                        children: Vec::new(),
+                    });
+                }
+            }
+            Token::End(name) => {
+                let Some(start) = open.pop() else {
+                    error = Some(
+                        InnerHTMLParseError::UnopenedTag {
+                            tag: name.to_string(),
+                        }
+                        .with_location(location),
+                    );
+                    return ControlFlow::Break(());
+                };
+                if !name.eq_ignore_ascii_case(&start.element.name) {
+                    error = Some(
+                        InnerHTMLParseError::MismatchedTags {
+                            start_tag: start.element.name,
+                            start_location: start.location,
+                            end_tag: name.to_string(),
+                        }
+                        .with_location(location),
+                    );
+                    return ControlFlow::Break(());
+                }
+                if buffering {
+                    tokens.push((token, location));
+                }
+                if let Some(index) = start.matched {
+                    let element = match stack_to_dom(tokens[index..].to_vec()) {
+                        Ok(nodes) => match nodes.into_iter().next() {
+                            Some(Node::Element(element)) => Some(element),
+                            _ => None,
+                        },
+                        Err(e) => {
+                            error = Some(e);
+                            return ControlFlow::Break(());
+                        }
+                    };
+                    if index == 0 {
+                        tokens.clear();
+                    }
+                    if let Some(element) = element {
+                        if f(element).is_break() {
+                            stopped = true;
+                            return ControlFlow::Break(());
+                        }
+                    }
+                }
+            }
+            _ => {
+                if buffering {
+                    tokens.push((token, location));
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    })?;
+
+    if let Some(error) = error {
+        return Err(error);
+    }
+    match open.pop() {
+        Some(start) if !stopped => Err(InnerHTMLParseError::UnclosedTag {
+            tag: start.element.name,
+        }
+        .with_location(start.location)),
+        _ => Ok(()),
+    }
+}
+
+/// Check if `element`, which is the last child read of the last of the
+/// `open` elements, or the last of `top_level` if there are none, matches.
+fn is_matching(
+    selector: &Selector,
+    element: &Element,
+    open: &[OpenElement],
+    top_level: &[Element],
+    needs_siblings: bool,
+) -> bool {
+    let siblings = |depth: usize| match depth {
+        _ if !needs_siblings => Siblings::None,
+        0 => Siblings::Preceding(top_level),
+        depth => Siblings::Preceding(&open[depth - 1].children),
+    };
+    let ancestors: Vec<(&Element, Siblings)> = open
+        .iter()
+        .enumerate()
+        .map(|(depth, parent)| (&parent.element, siblings(depth)))
+        .collect();
+    selector.matches_in(&Scope::with_ancestors(
+        element,
+        siblings(open.len()),
+        &ancestors,
+    ))
+}
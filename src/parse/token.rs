@@ -1,4 +1,4 @@
-use crate::parse::{attrs, InnerHTMLParseError};
+use crate::parse::{attrs, decode_entities, InnerHTMLParseError, ParseOptions};
 use crate::{Doctype, Element, Node};
 
 #[derive(Debug, Clone)]
@@ -13,37 +13,65 @@ pub enum Token {
     Doctype(Doctype),
     /// Like `<!-- comment -->`
     Comment(String),
+    /// Like `<![CDATA[x]]>`, in foreign content
+    CData(String),
     /// Any text
     Text(String),
+    /// A tag which could not be parsed, as it is written, see
+    /// [`ParseOptions::raw_on_error`]
+    Raw(String),
 }
 
 impl Token {
     pub fn from(tag: String) -> Result<Self, InnerHTMLParseError> {
-        if tag.ends_with("/>") {
+        if !tag.starts_with('<') {
+            Err(InnerHTMLParseError::InvalidTag {
+                tag,
+                reason: "Invalid tag",
+            })
+        } else if tag.ends_with("/>") {
             let tag_name_start = tag[1..tag.len()-2]
-                .chars()
-                .position(|x| x != ' ')
+                .find(|x: char| !x.is_ascii_whitespace())
                 .map(|x| x + 1)
-                .ok_or_else(|| InnerHTMLParseError::InvalidTag { tag: tag.clone(), reason: "Tag name cannot be all spaces after \"<\"" })?;
-            let tag_name_end_option = tag[tag_name_start..tag.len()]
-                .chars()
-                .position(|x| x == ' ');
+                .ok_or_else(|| InnerHTMLParseError::InvalidTag {
+                    tag: tag.clone(),
+                    reason: "Tag name cannot be all spaces after \"<\"",
+                })?;
+            let tag_name_end_option =
+                tag[tag_name_start..tag.len()].find(|x: char| x.is_ascii_whitespace());
             let tag_name_end = match tag_name_end_option {
                 Some(end) => end + tag_name_start,
                 None => tag.len() - 2,
             };
             let tag_name = tag[tag_name_start..tag_name_end].to_string();
             let attr_str = tag[tag_name_end..tag.len() - 2].trim().to_string();
-            Ok(Self::Closing(tag_name, attrs::parse(attr_str)))
+            Ok(Self::Closing(tag_name, parse_attrs(attr_str, &tag)?))
         } else if tag.starts_with("</") {
             Ok(Self::End(tag[2..tag.len() - 1].trim().to_string()))
         } else if tag.starts_with("<!--") {
             Ok(Self::from_comment(tag))
         } else if tag.starts_with("<!") {
-            Ok(Self::Doctype(Doctype::Html))
+            let is_doctype = tag
+                .get(2..9)
+                .is_some_and(|name| name.eq_ignore_ascii_case("doctype"));
+            match tag.as_str() {
+                "<!DOCTYPE html>" => Ok(Self::Doctype(Doctype::Html)),
+                _ if is_doctype => Ok(Self::Doctype(Doctype::Spelled(tag))),
+                _ => Err(InnerHTMLParseError::InvalidTag {
+                    tag,
+                    reason: "Only doctypes and comments can start with \"<!\"",
+                }),
+            }
         } else if tag.starts_with("<?") {
-            let attr = tag[2..tag.len() - 2].to_string();
-            let attr = attrs::parse(attr);
+            let attr = tag
+                .get(2..)
+                .and_then(|attr| attr.strip_suffix("?>"))
+                .ok_or_else(|| InnerHTMLParseError::InvalidTag {
+                    tag: tag.clone(),
+                    reason: "Xml declaration must end with \"?>\"",
+                })?
+                .to_string();
+            let attr = parse_attrs(attr, &tag)?;
             let version = attr
                 .iter()
                 .find(|(name, _)| name == "version")
@@ -53,20 +81,22 @@ impl Token {
             let encoding = attr
                 .iter()
                 .find(|(name, _)| name == "encoding")
-                .map(|x| x.1.to_string())
-                .ok_or_else(|| InnerHTMLParseError::InvalidTag { tag: tag.clone(), reason: "Cannot find encoding attribute in xml declaration" })?;
+                .map(|x| x.1.to_string());
+            let single_quoted = tag.find(['"', '\'']).map(|i| &tag[i..i + 1]) == Some("'");
 
-            Ok(Self::Doctype(Doctype::Xml { version, encoding }))
+            Ok(Self::Doctype(Doctype::Xml {
+                version,
+                encoding,
+                single_quoted,
+            }))
         } else if tag.starts_with('<') {
             let tag_name_start = tag[1..tag.len()-1]
-                .chars()
-                .position(|x| !x.is_ascii_whitespace())
+                .find(|x: char| !x.is_ascii_whitespace())
                 .map(|x| x + 1)
                 .ok_or_else(|| InnerHTMLParseError::InvalidTag { tag: tag.clone(), reason: "Tag name cannot be all spaces after \"<\"" })?;
 
-            let tag_name_end_option = tag[tag_name_start..tag.len()]
-                .chars()
-                .position(|x| x.is_ascii_whitespace());
+            let tag_name_end_option =
+                tag[tag_name_start..tag.len()].find(|x: char| x.is_ascii_whitespace());
 
             let tag_name_end = match tag_name_end_option {
                 Some(end) => end + tag_name_start,
@@ -74,12 +104,25 @@ impl Token {
             };
             let tag_name = tag[tag_name_start..tag_name_end].to_string();
             let attr_str = tag[tag_name_end..tag.len() - 1].trim().to_string();
-            Ok(Self::Start(tag_name, attrs::parse(attr_str)))
+            Ok(Self::Start(tag_name, parse_attrs(attr_str, &tag)?))
         } else {
             Err(InnerHTMLParseError::InvalidTag { tag, reason: "Invalid tag" })
         }
     }
 
+    /// Lowercase the tag name unless `options` says to preserve it.
+    pub fn with_tag_case(self, options: &ParseOptions) -> Self {
+        if options.preserve_tag_case {
+            return self;
+        }
+        match self {
+            Self::Start(name, attrs) => Self::Start(name.to_ascii_lowercase(), attrs),
+            Self::End(name) => Self::End(name.to_ascii_lowercase()),
+            Self::Closing(name, attrs) => Self::Closing(name.to_ascii_lowercase(), attrs),
+            token => token,
+        }
+    }
+
     #[inline]
     pub fn from_comment(comment: String) -> Self {
         Self::Comment(comment[4..comment.len() - 3].to_string())
@@ -90,7 +133,7 @@ impl Token {
     /// assert_eq!(Token::from_raw_text("hello &amp; goodbye"), Token::Text("hello & goodbye"));
     /// ```
     pub fn from_raw_text(text: String) -> Self {
-        Self::Text(html_escape::decode_html_entities(&text).into_owned())
+        Self::Text(decode_entities(text))
     }
 
     pub fn node(&self) -> Node {
@@ -103,6 +146,7 @@ impl Token {
                 name,
                 attrs,
                 children: Vec::new(),
+                dirty: false,
             }
             .into_node(),
 
@@ -110,6 +154,7 @@ impl Token {
                 name,
                 attrs: Vec::new(),
                 children: Vec::new(),
+                dirty: false,
             }
             .into_node(),
 
@@ -117,12 +162,15 @@ impl Token {
                 name,
                 attrs,
                 children: Vec::new(),
+                dirty: false,
             }
             .into_node(),
 
             Self::Doctype(doctype) => Node::Doctype(doctype),
             Self::Comment(comment) => Node::Comment(comment),
+            Self::CData(text) => Node::CData(text),
             Self::Text(text) => Node::Text(text),
+            Self::Raw(html) => Node::RawHTML(html),
         }
     }
 
@@ -132,18 +180,28 @@ impl Token {
                 name,
                 attrs,
                 children: Vec::new(),
+                dirty: false,
             },
             Self::End(name) => Element {
                 name,
                 attrs: Vec::new(),
                 children: Vec::new(),
+                dirty: false,
             },
             Self::Closing(name, attrs) => Element {
                 name,
                 attrs,
                 children: Vec::new(),
+                dirty: false,
             },
             _ => panic!("Cannot convert token to element"),
         }
     }
 }
+
+fn parse_attrs(attr_str: String, tag: &str) -> Result<Vec<(String, String)>, InnerHTMLParseError> {
+    attrs::parse(attr_str).map_err(|reason| InnerHTMLParseError::InvalidTag {
+        tag: tag.to_string(),
+        reason,
+    })
+}
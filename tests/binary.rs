@@ -0,0 +1,102 @@
+#![cfg(feature = "binary")]
+
+use html_editor::binary::{from_bytes, to_bytes, BinaryError, MAGIC, MAX_DEPTH, VERSION};
+use html_editor::operation::*;
+use html_editor::{parse, Doctype, Element, Node};
+
+#[test]
+fn round_trip() {
+    const HTML: &str = r#"<?xml version="1.0" encoding="UTF-8"?><!doctype html><html lang="en"><body a="" b="&amp;"><p>Ünïcode &lt;3<!-- c --></p><br><script>if (a < b) {}</script></body></html>"#;
+    let mut nodes = parse(HTML).unwrap();
+    nodes.insert_to(&Selector::from("p"), Node::RawHTML("<raw>".into()));
+    nodes.insert_to(&Selector::from("p"), Node::CData("x < y".into()));
+    nodes.push(Node::Doctype(Doctype::Html));
+    nodes.push(Node::Doctype(Doctype::Xml {
+        version: "1.1".into(),
+        encoding: None,
+        single_quoted: true,
+    }));
+
+    let bytes = to_bytes(&nodes).unwrap();
+    assert!(bytes.starts_with(&MAGIC));
+    assert_eq!(bytes[MAGIC.len()], VERSION);
+
+    let read = from_bytes(&bytes).unwrap();
+    assert_eq!(read, nodes);
+    assert!(read.query(&Selector::from("p")).unwrap().is_dirty());
+    assert_eq!(to_bytes(&read).unwrap(), bytes);
+}
+
+#[test]
+fn errors() {
+    let bytes = to_bytes(&parse("<p>Hi</p>").unwrap()).unwrap();
+
+    let mut version = bytes.clone();
+    version[MAGIC.len()] = VERSION + 1;
+    assert_eq!(
+        from_bytes(&version).unwrap_err(),
+        BinaryError::UnsupportedVersion(VERSION + 1)
+    );
+
+    assert_eq!(
+        from_bytes(b"<p>Hi</p>").unwrap_err(),
+        BinaryError::InvalidHeader
+    );
+    assert_eq!(
+        from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+        BinaryError::UnexpectedEnd
+    );
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(
+        from_bytes(&trailing).unwrap_err(),
+        BinaryError::TrailingBytes
+    );
+
+    let mut kind = bytes.clone();
+    kind[MAGIC.len() + 5] = 100;
+    assert_eq!(
+        from_bytes(&kind).unwrap_err(),
+        BinaryError::InvalidNodeKind(100)
+    );
+
+    let mut utf8 = bytes;
+    let last = utf8.len() - 1;
+    utf8[last] = 0xff;
+    assert_eq!(from_bytes(&utf8).unwrap_err(), BinaryError::InvalidUtf8);
+}
+
+#[test]
+fn nesting() {
+    let nested = |depth: usize| {
+        let mut nodes = Vec::new();
+        for _ in 0..depth {
+            nodes = vec![Element::new("a", vec![], nodes).into_node()];
+        }
+        nodes
+    };
+    let bytes = to_bytes(&nested(MAX_DEPTH)).unwrap();
+    assert_eq!(from_bytes(&bytes).unwrap(), nested(MAX_DEPTH));
+    assert_eq!(
+        to_bytes(&nested(MAX_DEPTH + 1)).unwrap_err(),
+        BinaryError::TooDeep
+    );
+
+    // Each level is a list of one new, so dirty, element named "a" without
+    // attributes, and the innermost one has no children.
+    let nested_bytes = |depth: usize| {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        for _ in 0..depth {
+            bytes.extend_from_slice(&[1, 0, 0, 0, 1, 1, 0, 0, 0, b'a', 0, 0, 0, 0]);
+        }
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        bytes
+    };
+    assert_eq!(nested_bytes(MAX_DEPTH), bytes);
+    assert_eq!(
+        from_bytes(&nested_bytes(MAX_DEPTH + 1)).unwrap_err(),
+        BinaryError::TooDeep
+    );
+}
@@ -55,3 +55,283 @@ fn remove() {
     let html = parse(HTML).unwrap().remove_by(&meta_selector).html();
     assert_eq!(html, REMOVED_HTML);
 }
+
+#[test]
+fn insert_relative_to_child() {
+    let mut nodes = parse("<tr><td>1</td><td class=\"last\">3</td></tr>").unwrap();
+    let tr = nodes[0].as_element_mut().unwrap();
+
+    let cell = |text: &str| Node::new_element("td", vec![], vec![Node::Text(text.to_string())]);
+    assert!(tr.insert_before_child_matching(&Selector::from(".last"), cell("2")));
+    assert!(tr.insert_after_child_matching(&Selector::from(".last"), cell("4")));
+    assert!(!tr.insert_after_child_matching(&Selector::from("th"), cell("5")));
+    tr.insert_after_child_at(0, cell("1.5"));
+
+    assert_eq!(
+        tr.html(),
+        r#"<tr><td>1</td><td>1.5</td><td>2</td><td class="last">3</td><td>4</td></tr>"#
+    );
+}
+
+#[test]
+fn detach() {
+    let mut dom = parse(
+        r#"<div><p class="note">1</p><section><p class="note">2<p class="note">3</p></p></section><p class="note">4</p></div>"#,
+    )
+    .unwrap();
+
+    let notes = dom.detach(&Selector::from(".note"));
+    assert_eq!(
+        notes.html(),
+        r#"<p class="note">1</p><p class="note">2<p class="note">3</p></p><p class="note">4</p>"#
+    );
+    assert_eq!(dom.html(), "<div><section></section></div>");
+}
+
+#[test]
+fn remove_descendants() {
+    let html = parse("<nav><a>1</a></nav><p><a>2</a></p>")
+        .unwrap()
+        .remove_by(&Selector::from("nav a"))
+        .html();
+    assert_eq!(html, "<nav></nav><p><a>2</a></p>");
+}
+
+#[test]
+fn repair_void_elements() {
+    let mut nodes = vec![Node::new_element(
+        "p",
+        vec![],
+        vec![
+            Node::new_element(
+                "IMG",
+                vec![("src", "a.png")],
+                vec![
+                    Node::Text("Caption".into()),
+                    Node::new_element("br", vec![], vec![Node::new_element("b", vec![], vec![])]),
+                ],
+            ),
+            Node::Text("!".into()),
+        ],
+    )];
+
+    assert_eq!(
+        nodes.repair_void_elements().html(),
+        r#"<p><IMG src="a.png">Caption<br><b></b>!</p>"#
+    );
+}
+
+#[test]
+fn walk() {
+    // Merge adjacent text nodes, and stop at the first `<hr>`
+    let mut nodes = parse("<div><p>a</p>b<hr>c</div>").unwrap();
+    nodes[0]
+        .as_element_mut()
+        .unwrap()
+        .children
+        .insert(1, Node::Text("!".into()));
+    nodes[0].as_element_mut().unwrap().children[0]
+        .as_element_mut()
+        .unwrap()
+        .children
+        .push(Node::Text("a".into()));
+
+    let mut visited = Vec::new();
+    nodes.walk(|siblings, index| {
+        if let (Some(Node::Text(text)), Some(Node::Text(next))) =
+            (siblings.get(index), siblings.get(index + 1))
+        {
+            let merged = format!("{}{}", text, next);
+            siblings[index] = Node::Text(merged);
+            siblings.remove(index + 1);
+        }
+        visited.push(siblings[index].html());
+        siblings[index].name() != Some("hr")
+    });
+
+    assert_eq!(nodes.html(), "<div><p>aa</p>!b<hr>c</div>");
+    assert_eq!(
+        visited,
+        vec![
+            "<div><p>aa</p>!b<hr>c</div>",
+            "<p>aa</p>",
+            "aa",
+            "!b",
+            "<hr>"
+        ]
+    );
+    assert!(nodes[0].as_element().unwrap().is_dirty());
+}
+
+#[test]
+fn replace_with() {
+    let target = Node::new_element("span", vec![], vec![Node::Text("x".into())]);
+
+    let mut nodes =
+        parse("<p><span>1</span><span><span>2</span></span></p><span>3</span>").unwrap();
+    nodes.replace_with(&Selector::from("span:first-of-type"), target.clone());
+    assert_eq!(
+        nodes.html(),
+        "<p><span>x</span><span><span>x</span></span></p><span>x</span>"
+    );
+
+    // The nodes which replaced others are not edited again
+    let mut nodes = parse("<ul><li>1</li><li>2</li></ul>").unwrap();
+    let ul = nodes[0].as_element_mut().unwrap();
+    ul.replace_with(
+        &Selector::from("li"),
+        Node::new_element("li", vec![], vec![target]),
+    )
+    .remove_by(&Selector::from("li:last-of-type"));
+    assert_eq!(ul.html(), "<ul><li><span>x</span></li></ul>");
+    assert!(ul.is_dirty());
+}
+
+#[test]
+fn edits_match_before_editing() {
+    // The descendants are matched against the siblings before the edit
+    const HTML: &str = "<h2>Title</h2><section><p>a</p></section><p>b</p>";
+    let selector = Selector::from("h2, section:nth-child(2) p");
+
+    let mut nodes = parse(HTML).unwrap();
+    nodes.remove_by(&selector);
+    assert_eq!(nodes.html(), "<section></section><p>b</p>");
+
+    let mut nodes = parse(HTML).unwrap();
+    nodes.replace_with(&selector, Node::new_element("hr", vec![], vec![]));
+    assert_eq!(nodes.html(), "<hr><section><hr></section><p>b</p>");
+
+    let mut nodes = parse(HTML).unwrap();
+    let detached = nodes.detach(&selector);
+    assert_eq!(nodes.html(), "<section></section><p>b</p>");
+    assert_eq!(detached.html(), "<h2>Title</h2><p>a</p>");
+}
+
+#[test]
+fn unwrap_elements_where() {
+    let html = r#"<div><span>a<span><span>b</span></span></span><span style="color: red">c<span>d</span></span></div>"#;
+    let mut nodes = parse(html).unwrap();
+    let div = nodes[0].as_element_mut().unwrap();
+
+    let mut checked = 0;
+    div.unwrap_elements_where(|el| {
+        checked += 1;
+        el.name == "span" && el.attrs.is_empty()
+    });
+    assert_eq!(
+        div.html(),
+        r#"<div>ab<span style="color: red">cd</span></div>"#
+    );
+    assert_eq!(checked, 5);
+    assert!(div.is_dirty());
+
+    // An element left empty by unwrapping its children can be unwrapped too
+    let mut nodes = parse("<p><b><i></i></b></p>").unwrap();
+    nodes.unwrap_elements_where(|el| el.name != "p" && el.children.is_empty());
+    assert_eq!(nodes.html(), "<p></p>");
+}
+
+#[test]
+fn map_text() {
+    const HTML: &str =
+        r#"<div class="x">Hello <b>wörld</b><!-- note --><STYLE>b { color: red }</STYLE></div>"#;
+
+    let mut nodes = parse(HTML).unwrap();
+    nodes.map_text(|text| text.to_uppercase());
+    assert_eq!(
+        nodes.html(),
+        r#"<div class="x">HELLO <b>WÖRLD</b><!-- note --><STYLE>b { color: red }</STYLE></div>"#
+    );
+
+    let options = MapTextOptions {
+        into_raw_text: true,
+    };
+    let mut texts = Vec::new();
+    nodes[0].map_text_with(&options, |text| {
+        texts.push(text.to_string());
+        text.to_lowercase()
+    });
+    assert_eq!(texts, vec!["HELLO ", "WÖRLD", "b { color: red }"]);
+
+    let nodes = parse(HTML)
+        .unwrap()
+        .with_mapped_text(|text| text.replace('l', "1"));
+    assert_eq!(
+        nodes.html(),
+        r#"<div class="x">He11o <b>wör1d</b><!-- note --><STYLE>b { color: red }</STYLE></div>"#
+    );
+}
+
+#[test]
+fn node_edits() {
+    let mut node = parse("<ul><li>1</li><li>2</li></ul>").unwrap().remove(0);
+    node.remove_by(&Selector::from("li:last-of-type"))
+        .insert_to(&Selector::from("ul"), Node::Text("!".into()));
+    assert_eq!(node.html(), "<ul><li>1</li>!</ul>");
+
+    let mut text = Node::Text("Hi".into());
+    assert!(text
+        .remove_by(&Selector::from("p"))
+        .detach(&Selector::from("p"))
+        .is_empty());
+    assert_eq!(text.trim().html(), "Hi");
+}
+
+#[test]
+fn replace_attr_substring() {
+    let html = r#"<div data-src="http://old.cdn.com/x.js"><img src="http://old.cdn.com/a.png"><SCRIPT SRC="//old.cdn.com/old.cdn.com.js"></SCRIPT><a href="http://old.cdn.com/">old.cdn.com</a></div>"#;
+    let mut nodes = parse(html).unwrap();
+
+    nodes.replace_attr_substring(&["src"], "old.cdn.com", "new.cdn.com");
+    assert_eq!(
+        nodes.html(),
+        r#"<div data-src="http://old.cdn.com/x.js"><img src="http://new.cdn.com/a.png"><SCRIPT SRC="//new.cdn.com/new.cdn.com.js"></SCRIPT><a href="http://old.cdn.com/">old.cdn.com</a></div>"#
+    );
+
+    let div = nodes[0].as_element_mut().unwrap();
+    div.replace_attr_substring(&["data-src", "href"], "http://", "https://");
+    assert_eq!(div.get_attr("data-src"), Some("https://old.cdn.com/x.js"));
+    assert_eq!(
+        div.query(&Selector::from("a")).unwrap().get_attr("href"),
+        Some("https://old.cdn.com/")
+    );
+
+    let before = nodes.html();
+    nodes.replace_attr_substring(&["src", "href"], "", "x");
+    assert_eq!(nodes.html(), before);
+}
+
+#[test]
+fn replace_at() {
+    let mut nodes = parse("<ul><li>1</li><li>2</li></ul>text").unwrap();
+    let paths = nodes.query_all_paths(&Selector::from("li"));
+
+    for (n, path) in paths.iter().enumerate() {
+        let old = nodes.replace_at(path, Node::Text(n.to_string())).unwrap();
+        assert_eq!(old.name(), Some("li"));
+    }
+    assert_eq!(nodes.html(), "<ul>01</ul>text");
+    assert!(nodes[0].as_element().unwrap().is_dirty());
+
+    // The paths no longer lead to elements
+    assert!(nodes
+        .replace_at(&[0, 0, 0], Node::Text("x".into()))
+        .is_none());
+    assert!(nodes.replace_at(&[0, 2], Node::Text("x".into())).is_none());
+    assert!(nodes.replace_at(&[], Node::Text("x".into())).is_none());
+
+    let old = nodes
+        .replace_at(&[1], Node::new_element("br", vec![], vec![]))
+        .unwrap();
+    assert!(matches!(old, Node::Text(text) if text == "text"));
+
+    let ul = nodes[0].as_element_mut().unwrap();
+    ul.replace_at(&[1], Node::Text("2".into()));
+    assert_eq!(ul.html(), "<ul>02</ul>");
+
+    let mut node = nodes.remove(0);
+    let old = node.replace_at(&[], Node::Comment("ul".into())).unwrap();
+    assert_eq!(old.html(), "<ul>02</ul>");
+    assert_eq!(node.html(), "<!--ul-->");
+    assert!(node.replace_at(&[0], Node::Text("x".into())).is_none());
+}
@@ -0,0 +1,332 @@
+use html_editor::operation::{Editable, Htmlifiable, Queryable, Selector};
+use html_editor::{parse, BooleanAttributes, Element, Node};
+
+#[test]
+fn attributes_map() {
+    let element = Element::new(
+        "input",
+        vec![("value", "a"), ("type", "text"), ("value", "b")],
+        vec![],
+    );
+    let attrs = element.attributes_map();
+
+    assert_eq!(attrs.keys().collect::<Vec<_>>(), vec!["type", "value"]);
+    assert_eq!(attrs["value"], "a");
+}
+
+#[cfg(feature = "indexmap")]
+#[test]
+fn attributes_index_map() {
+    let element = Element::new(
+        "img",
+        vec![("src", "a.png"), ("alt", ""), ("src", "b.png")],
+        vec![],
+    );
+    let attrs = element.attributes_index_map();
+
+    assert_eq!(attrs.keys().collect::<Vec<_>>(), vec!["src", "alt"]);
+    assert_eq!(attrs["src"], "a.png");
+}
+
+#[test]
+fn first_and_last_child() {
+    let mut element = Element::new(
+        "p",
+        vec![],
+        vec![
+            Node::Text("  Hello ".to_string()),
+            Node::new_element("b", vec![], vec![]),
+            Node::Comment(" end ".to_string()),
+        ],
+    );
+
+    if let Some(Node::Text(text)) = element.first_child_mut() {
+        *text = text.trim_start().to_string();
+    }
+    assert!(matches!(element.first_child(), Some(Node::Text(text)) if text == "Hello "));
+    assert!(matches!(element.last_child(), Some(Node::Comment(_))));
+
+    element.children.clear();
+    assert!(element.first_child().is_none());
+    assert!(element.last_child_mut().is_none());
+}
+
+#[test]
+fn inner_text() {
+    let nodes = parse(
+        r#"
+        <article>
+            <h1>Title</h1>
+            <p>First   line,<br>second line.</p>
+            <script>ignored()</script>
+            <!-- ignored -->
+            <pre>  keep
+  this</pre>
+            <ul><li>One</li><li>Two &amp; two</li></ul>
+        </article>"#,
+    )
+    .unwrap();
+    let mut article = nodes[1].as_element().unwrap().clone();
+
+    let text = "Title\nFirst line,\nsecond line.\n  keep\n  this\nOne\nTwo & two";
+    assert_eq!(article.inner_text(), text);
+    assert!(matches!(article.to_text_node(), Node::Text(t) if t == text));
+
+    article.flatten_to_text();
+    assert!(matches!(&article.children[..], [Node::Text(t)] if t == text));
+}
+
+#[test]
+fn append_html() {
+    let mut nodes = parse("<ul><li>0</li></ul>").unwrap();
+    let ul = nodes[0].as_element_mut().unwrap();
+    ul.append_html("<li>1</li>").unwrap();
+    ul.append_html(" <li data-n=\"2\">2 &amp; more</li>")
+        .unwrap();
+    assert_eq!(
+        nodes.html(),
+        r#"<ul><li>0</li><li>1</li> <li data-n="2">2 &amp; more</li></ul>"#
+    );
+
+    let mut script = Element::new("script", vec![], vec![]);
+    script.append_html("if (a && b < c) {}").unwrap();
+    assert_eq!(script.html(), "<script>if (a && b < c) {}</script>");
+}
+
+#[test]
+fn reorder_attributes() {
+    let mut element = Element::new(
+        "input",
+        vec![
+            ("type", "text"),
+            ("name", "q"),
+            ("class", "a"),
+            ("class", "b"),
+        ],
+        vec![],
+    );
+
+    assert!(element.move_attr_to_front("class"));
+    assert_eq!(
+        element.attrs,
+        attrs(&[
+            ("class", "a"),
+            ("type", "text"),
+            ("name", "q"),
+            ("class", "b")
+        ])
+    );
+    assert!(element.move_attr_to_front("class"));
+    assert!(!element.move_attr_to_front("id"));
+    assert_eq!(element.attrs[0], ("class".into(), "a".into()));
+
+    element.sort_attrs_by(|name, _| name.to_string());
+    assert_eq!(
+        element.attrs,
+        attrs(&[
+            ("class", "a"),
+            ("class", "b"),
+            ("name", "q"),
+            ("type", "text")
+        ])
+    );
+
+    element.sort_attrs_by(|_, value| std::cmp::Reverse(value.len()));
+    assert_eq!(
+        element.attrs,
+        attrs(&[
+            ("type", "text"),
+            ("class", "a"),
+            ("class", "b"),
+            ("name", "q")
+        ])
+    );
+}
+
+fn attrs(attrs: &[(&str, &str)]) -> Vec<(String, String)> {
+    attrs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn node_accessors() {
+    use html_editor::operation::{Queryable, Selector};
+
+    let nodes = parse(r#"<nav><a href="/">Home</a> <a>About</a></nav>"#).unwrap();
+    let nav = &nodes[0];
+    assert_eq!(nav.name(), Some("nav"));
+
+    let children = nav.children().unwrap();
+    let hrefs: Vec<_> = children
+        .iter()
+        .map(|child| child.get_attr("href"))
+        .collect();
+    assert_eq!(hrefs, vec![Some("/"), None, None]);
+    let names: Vec<_> = children.iter().map(Node::name).collect();
+    assert_eq!(names, vec![Some("a"), None, Some("a")]);
+    assert!(children[1].children().is_none());
+
+    let about = nav.query_all(&Selector::from("a"))[1];
+    assert_eq!(about.get_attr("href"), None);
+}
+
+#[test]
+fn id() {
+    let mut element = Element::new("p", vec![("ID", "upper"), ("id", "a"), ("id", "b")], vec![]);
+    assert_eq!(element.id(), Some("a"));
+
+    element.set_id(String::from("c"));
+    assert_eq!(
+        element.attrs,
+        attrs(&[("ID", "upper"), ("id", "c"), ("id", "b")])
+    );
+
+    element.set_id("");
+    assert_eq!(element.id(), None);
+    assert_eq!(element.attrs, attrs(&[("ID", "upper")]));
+
+    element.set_id("d");
+    assert_eq!(element.attrs, attrs(&[("ID", "upper"), ("id", "d")]));
+}
+
+#[test]
+fn whitespace_text() {
+    for text in ["", " ", "\n\t\r\x0C"] {
+        assert!(
+            Node::Text(text.to_string()).is_whitespace_text(),
+            "{:?}",
+            text
+        );
+    }
+    for text in ["\u{a0}", "\u{2003}", "\x0B", " a "] {
+        assert!(
+            !Node::Text(text.to_string()).is_whitespace_text(),
+            "{:?}",
+            text
+        );
+    }
+
+    // Non-breaking spaces are content, so they are kept by `trim`
+    let mut nodes = parse("<p>\n  <b>&nbsp;</b>\n  <i>\r\n</i></p>").unwrap();
+    assert_eq!(nodes.trim().html(), "<p><b>\u{a0}</b><i></i></p>");
+}
+
+#[test]
+fn has_attr() {
+    let element = Element::new(
+        "input",
+        vec![("Type", "Text"), ("type", "number"), ("value", "")],
+        vec![],
+    );
+    assert!(element.has_attr("TYPE"));
+    assert!(element.has_attr("value"));
+    assert!(!element.has_attr("val"));
+
+    assert!(element.has_attr_value("type", "Text"));
+    assert!(!element.has_attr_value("type", "number"));
+    assert!(!element.has_attr_value("type", "text"));
+    assert!(element.has_attr_value_ignore_case("type", "TEXT"));
+    assert!(element.has_attr_value("value", ""));
+}
+
+#[test]
+fn normalize_boolean_attributes() {
+    let mut nodes =
+        parse(r#"<div><input checked><option selected="SELECTED"></option></div>"#).unwrap();
+    let div = nodes[0].as_element_mut().unwrap();
+
+    div.normalize_boolean_attributes(BooleanAttributes::Valued);
+    assert_eq!(
+        div.html(),
+        r#"<div><input checked="checked"><option selected="selected"></option></div>"#
+    );
+
+    div.normalize_boolean_attributes(BooleanAttributes::BareNames);
+    assert_eq!(
+        div.html(),
+        r#"<div><input checked><option selected></option></div>"#
+    );
+    assert!(!div.is_dirty());
+}
+
+#[test]
+fn classes() {
+    let mut nodes = parse("<p class=\"btn active\n\tbtn  big active\">Hi</p>").unwrap();
+    let p = nodes[0].as_element_mut().unwrap();
+    assert_eq!(
+        p.classes().collect::<Vec<_>>(),
+        vec!["btn", "active", "btn", "big", "active"]
+    );
+    assert!(p.has_class("big"));
+    assert!(p.query(&Selector::from(".btn.big")).is_some());
+
+    p.dedup_classes();
+    assert_eq!(p.get_attr("class"), Some("btn active big"));
+    p.sort_classes();
+    assert_eq!(p.get_attr("class"), Some("active big btn"));
+
+    let mut dups = Element::new("i", vec![("class", "b a b")], vec![]);
+    dups.sort_classes();
+    assert_eq!(dups.get_attr("class"), Some("a b b"));
+
+    let mut none = Element::new("i", vec![], vec![]);
+    none.dedup_classes();
+    assert!(none.attrs.is_empty());
+    assert!(!none.has_class(""));
+}
+
+#[test]
+fn move_children_to() {
+    let mut from = Element::new(
+        "div",
+        vec![],
+        vec![
+            Node::Text("a".to_string()),
+            Node::new_element("b", vec![], vec![]),
+        ],
+    );
+    let mut dest = Element::new("p", vec![], vec![Node::Text("0".to_string())]);
+
+    from.move_children_to(&mut dest);
+    assert!(from.children.is_empty());
+    assert!(from.is_dirty() && dest.is_dirty());
+    assert_eq!(dest.html(), "<p>0a<b></b></p>");
+
+    dest.mark_clean();
+    from.move_children_to(&mut dest);
+    assert!(!dest.is_dirty());
+    assert_eq!(dest.children.len(), 3);
+}
+
+#[test]
+fn ensure_attr() {
+    let mut nodes = parse(r#"<img ALT="Logo" src="logo.png">"#).unwrap();
+    let img = nodes[0].as_element_mut().unwrap();
+
+    assert_eq!(img.ensure_attr("alt", ""), "Logo");
+    assert_eq!(img.ensure_attr("loading", String::from("lazy")), "lazy");
+    assert_eq!(img.ensure_attr("loading", "eager"), "lazy");
+    assert_eq!(img.attrs.len(), 3);
+    assert_eq!(img.get_attr("ALT"), Some("Logo"));
+    assert_eq!(
+        img.attrs.last(),
+        Some(&("loading".to_string(), "lazy".to_string()))
+    );
+}
+
+#[test]
+fn equality_ignores_dirty() {
+    let parsed = parse("<p class=\"a\">x</p>").unwrap();
+    let created = vec![Node::new_element(
+        "p",
+        vec![("class", "a")],
+        vec![Node::Text("x".into())],
+    )];
+    assert!(!parsed[0].as_element().unwrap().is_dirty());
+    assert!(created[0].as_element().unwrap().is_dirty());
+    assert_eq!(parsed, created);
+    assert_eq!(format!("{:?}", parsed), format!("{:?}", created));
+    assert_ne!(parsed, parse("<p class=\"b\">x</p>").unwrap());
+}
@@ -19,14 +19,13 @@ fn test_parse() {
     let title_selector = Selector::from("title");
 
     let Some(title) = html.query(&title_selector) else {
-        assert!(false, "title selector failed to match");
-        return;
+        panic!("title selector failed to match");
     };
     assert_eq!(title.name, "title");
 
-    match title.children.get(0) {
+    match title.children.first() {
         Some(Node::Text(title_content)) => assert_eq!(title_content, "I <3 \"escaping\""),
-        _ => assert!(false, "<title> with no text child"),
+        _ => panic!("<title> with no text child"),
     }
 
     let div_selector = Selector::from("#testee");
@@ -36,11 +35,11 @@ fn test_parse() {
             assert_eq!(
                 div.attrs,
                 vec![
-                    ("attr".into(), "id-with-\"quotes\"-inside".into()),
                     ("id".into(), "testee".into()),
+                    ("attr".into(), "id-with-\"quotes\"-inside".into()),
                 ]);
         }
-        None => assert!(false, "div selector failed to match")
+        None => panic!("div selector failed to match"),
     }
 }
 
@@ -48,7 +47,7 @@ fn test_parse() {
 fn test_generate() {
     let element = Element::new(
         "dummy-tag",
-        vec![("attr-1".into(), "attribute containing < and \" and &".into())],
+        vec![("attr-1", "attribute containing < and \" and &")],
         vec![Node::Text("fake <tag>".into())],
     );
 
@@ -73,32 +72,24 @@ fn no_unescapes_in_script_and_style() {
     let script_selector = Selector::from("script");
 
     let Some(script) = html.query(&script_selector) else {
-        assert!(false, "script selector failed to match");
-        unreachable!()
+        panic!("script selector failed to match");
     };
     assert_eq!(script.name, "script");
 
-    match script.children.get(0) {
+    match script.children.first() {
         Some(Node::Text(script_content)) => assert_eq!(script_content, r#"let text = "this tag shouldn't be escaped -> <p> hi </p>""#),
-        _ => {
-            assert!(false, "script had no text children");
-            return;
-        }
+        _ => panic!("script had no text children"),
     }
 
     let style_selector = Selector::from("style");
 
     let Some(style) = html.query(&style_selector) else {
-        assert!(false, "Couldn't find style");
-        return;
+        panic!("Couldn't find style");
     };
 
-    match style.children.get(0) {
+    match style.children.first() {
         Some(Node::Text(style_content)) => assert_eq!(style_content, r#"main:before { content: "fake <b>tag</b>"; }"#),
-        _ => {
-            assert!(false, "style had no text children");
-            return;
-        }
+        _ => panic!("style had no text children"),
     }
 }
 
@@ -124,3 +115,53 @@ fn no_escapes_in_script_and_style() {
     let generated = element.html();
     assert_eq!(generated, r#"<head><script>let text = "this tag shouldn't be escaped -> <p> hi </p>"</script><style>main:before { content: "fake <b>tag</b>"; }</style></head>"#);
 }
+
+#[test]
+fn entities_without_semicolon() {
+    let text = |html: &str| match parse(html).unwrap().first() {
+        Some(Node::Text(text)) => text.clone(),
+        _ => panic!("{html:?} did not parse to a text node"),
+    };
+
+    assert_eq!(text("&copy 2020"), "\u{a9} 2020");
+    assert_eq!(text("&amp&amp"), "&&");
+    assert_eq!(text("&lt3"), "<3");
+    assert_eq!(text("&notit;"), "\u{ac}it;");
+    assert_eq!(text("&notin;"), "\u{2209}");
+    assert_eq!(text("&#169 and &#xA9"), "\u{a9} and \u{a9}");
+    // `hellip` always needs its semicolon
+    assert_eq!(text("&hellip and &#x"), "&hellip and &#x");
+    assert_eq!(text("&unknown; &amp;"), "&unknown; &");
+
+    let nodes = parse("<p>Tom &amp Jerry &copy 2020</p>").unwrap();
+    assert_eq!(nodes.html(), "<p>Tom &amp; Jerry \u{a9} 2020</p>");
+
+    // Attribute values keep references followed by `=` or alphanumerics as is
+    let nodes = parse(r#"<a href="?a=1&copy=2&amp;b=3&amp c=4"></a>"#).unwrap();
+    let Some(Node::Element(a)) = nodes.first() else {
+        panic!("<a> did not parse to an element");
+    };
+    assert_eq!(a.attrs[0].1, "?a=1&copy=2&b=3& c=4");
+    assert_eq!(
+        nodes.html(),
+        r#"<a href="?a=1&amp;copy=2&amp;b=3&amp; c=4"></a>"#
+    );
+}
+
+#[test]
+fn no_entity_decoding_in_script_and_style() {
+    const HTML: &str = r#"<script>if (a &amp;&amp; b && c &lt; d) {}</script><style>.a { content: "&copy &amp;"; }</style>"#;
+
+    let nodes = parse(HTML).unwrap();
+    let text = |selector: &str| match nodes.query(&Selector::from(selector)) {
+        Some(element) => match element.children.as_slice() {
+            [Node::Text(text)] => text.clone(),
+            children => panic!("<{selector}> has the children {children:?}"),
+        },
+        None => panic!("{selector} selector failed to match"),
+    };
+
+    assert_eq!(text("script"), "if (a &amp;&amp; b && c &lt; d) {}");
+    assert_eq!(text("style"), r#".a { content: "&copy &amp;"; }"#);
+    assert_eq!(nodes.html(), HTML);
+}
@@ -1,5 +1,5 @@
 use html_editor::operation::*;
-use html_editor::parse;
+use html_editor::{parse, parse_with_options, Doctype, Node, ParseOptions};
 
 const HTML: &str = r#"
     <div>
@@ -21,3 +21,307 @@ fn trimmed_html() {
         r#"<div><span id="class">Hello</span><span class="id">World</span></div>"#
     );
 }
+
+#[test]
+fn empty_elements() {
+    let nodes = vec![
+        Node::new_element("my-icon", vec![("name", "x")], vec![]),
+        Node::new_element("my-label", vec![], vec![Node::Text("Hi".to_string())]),
+        Node::new_element("br", vec![], vec![]),
+    ];
+
+    let html_void = SerializeOptions {
+        void_tags: vec!["my-icon".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(
+        nodes.html_with(&html_void),
+        r#"<my-icon name="x"><my-label>Hi</my-label><br></br>"#
+    );
+
+    assert_eq!(
+        nodes.html_with(&SerializeOptions::xml()),
+        r#"<my-icon name="x"/><my-label>Hi</my-label><br/>"#
+    );
+
+    let paired = SerializeOptions {
+        empty_elements: EmptyElements::Paired,
+        ..Default::default()
+    };
+    assert_eq!(
+        nodes.html_with(&paired),
+        r#"<my-icon name="x"></my-icon><my-label>Hi</my-label><br></br>"#
+    );
+}
+
+#[test]
+fn namespaced_attributes() {
+    let svg = Node::new_element(
+        "svg",
+        vec![("xmlns:xlink", "http://www.w3.org/1999/xlink")],
+        vec![Node::new_element(
+            "use",
+            vec![("xlink:href", "#icon")],
+            vec![],
+        )],
+    );
+    assert_eq!(
+        svg.html(),
+        r##"<svg xmlns:xlink="http://www.w3.org/1999/xlink"><use xlink:href="#icon"></use></svg>"##
+    );
+
+    const HTML: &str =
+        r##"<svg><use xlink:href="#icon"></use><text xml:lang="en">Hi</text></svg>"##;
+    assert_eq!(parse(HTML).unwrap().html(), HTML);
+}
+
+#[test]
+fn dirty_format() {
+    const HTML: &str = "<body>\n\t<nav>  <a>Home</a>  </nav>\n\t<ul>\n\t\t<li>1</li>\n\t</ul>\n\t<pre> x\n  y</pre>\n</body>";
+    let options = SerializeOptions {
+        format: Format::Dirty("  ".into()),
+        ..Default::default()
+    };
+
+    let mut nodes = parse(HTML).unwrap();
+    assert_eq!(nodes.html_with(&options), HTML);
+
+    nodes.insert_to(
+        &Selector::from("ul"),
+        Node::new_element("li", vec![], vec![Node::Text(" 2 ".into())]),
+    );
+    nodes.insert_to(
+        &Selector::from("pre"),
+        Node::new_element("b", vec![], vec![]),
+    );
+    assert_eq!(
+        nodes.html_with(&options),
+        "<body>\n\t<nav>  <a>Home</a>  </nav>\n\t<ul>\n\t  <li>1</li>\n\t  <li>2</li>\n\t</ul>\n\t<pre> x\n  y<b></b></pre>\n</body>"
+    );
+    // The content is the same with every format
+    assert_eq!(
+        nodes.html(),
+        "<body>\n\t<nav>  <a>Home</a>  </nav>\n\t<ul>\n\t\t<li>1</li>\n\t<li> 2 </li></ul>\n\t<pre> x\n  y<b></b></pre>\n</body>"
+    );
+
+    let mut nodes = parse(HTML).unwrap();
+    nodes.trim();
+    assert_eq!(
+        nodes.html_with(&options),
+        "<body>\n  <nav>\n    <a>Home</a>\n  </nav>\n  <ul>\n    <li>1</li>\n  </ul>\n  <pre> x\n  y</pre>\n</body>"
+    );
+}
+
+#[test]
+fn preserve_doctype() {
+    const HTML: &str = "<!doctype html><html></html>";
+    let nodes = parse(HTML).unwrap();
+    assert_eq!(nodes.html(), "<!DOCTYPE html><html></html>");
+
+    let options = SerializeOptions {
+        preserve_doctype: true,
+        ..Default::default()
+    };
+    assert_eq!(nodes.html_with(&options), HTML);
+
+    let legacy = r#"<!DOCTYPE html PUBLIC "-//W3C//DTD HTML 4.01//EN">"#;
+    assert_eq!(parse(legacy).unwrap().html_with(&options), legacy);
+
+    let built = vec![Node::Doctype(Doctype::Html)];
+    assert_eq!(built.html_with(&options), "<!DOCTYPE html>");
+
+    // Only the other spellings are kept
+    let nodes = parse("<!DOCTYPE html>").unwrap();
+    assert!(matches!(nodes[0], Node::Doctype(Doctype::Html)));
+    let nodes = parse("<!doctype html>").unwrap();
+    assert!(
+        matches!(&nodes[0], Node::Doctype(Doctype::Spelled(doctype)) if doctype == "<!doctype html>")
+    );
+
+    // Other markup declarations are not doctypes
+    for html in ["<!foo>", "<!ELEMENT br EMPTY>", "<!doc>"] {
+        assert!(parse(html).is_err());
+        let options = ParseOptions {
+            raw_on_error: true,
+            ..Default::default()
+        };
+        assert!(
+            matches!(&parse_with_options(html, &options).unwrap()[0], Node::RawHTML(raw) if raw == html)
+        );
+    }
+}
+
+#[test]
+fn custom_serializers() {
+    let nodes = parse(r#"<div><Card title="T"><b>Body</b></Card><card></card></div>"#).unwrap();
+
+    let mut options = SerializeOptions::default();
+    options.custom.insert(
+        "card".to_string(),
+        std::sync::Arc::new(|card: &html_editor::Element| {
+            let title = card.attrs.first().map_or("", |(_, title)| title.as_str());
+            format!(
+                "<section><h2>{}</h2>{}</section>",
+                title,
+                card.children.html()
+            )
+        }),
+    );
+    assert_eq!(
+        nodes.html_with(&options),
+        "<div><section><h2>T</h2><b>Body</b></section><section><h2></h2></section></div>"
+    );
+    assert_eq!(
+        nodes.html(),
+        r#"<div><Card title="T"><b>Body</b></Card><card></card></div>"#
+    );
+    assert!(format!("{:?}", options).contains(r#"custom: ["card"]"#));
+
+    // The options can be cloned and shared between threads
+    let cloned = options.clone();
+    let html = std::thread::spawn(move || nodes.html_with(&cloned))
+        .join()
+        .unwrap();
+    assert_eq!(
+        html,
+        "<div><section><h2>T</h2><b>Body</b></section><section><h2></h2></section></div>"
+    );
+}
+
+#[test]
+fn text_location() {
+    // `prettify` cuts the source at the locations of the tokens, so each
+    // text has to start right after the tag before it
+    assert_eq!(
+        html_editor::prettify("<div>\u{e9}a<br>b c</div>", "  ").unwrap(),
+        "<div>\n  \u{e9}a\n  <br>\n  b c\n</div>"
+    );
+    assert_eq!(
+        html_editor::prettify("x<p>y</p>", "  ").unwrap(),
+        "x\n<p>y</p>"
+    );
+}
+
+#[test]
+fn prettify_keeps_content() {
+    const HTML: &str = "<!doctype html><html><body>\n<p title=\"a &amp b\" data-x='&quot;'>&nbsp;Tom &amp Jerry&#160;</p><br><pre> a\n <b>b</b></pre>\n<script>if (a &amp;&amp; b) {}</script><!-- &copy; --></body></html>";
+    let pretty = html_editor::prettify(HTML, "\t").unwrap();
+    assert_eq!(
+        pretty,
+        "<!doctype html>\n<html>\n\t<body>\n\t\t<p title=\"a &amp b\" data-x='&quot;'>&nbsp;Tom &amp Jerry&#160;</p>\n\t\t<br>\n\t\t<pre> a\n <b>b</b></pre>\n\t\t<script>if (a &amp;&amp; b) {}</script>\n\t\t<!-- &copy; -->\n\t</body>\n</html>"
+    );
+    assert_eq!(html_editor::prettify(&pretty, "\t").unwrap(), pretty);
+
+    // The layout is the same as the one of `Format::Pretty`
+    let options = SerializeOptions {
+        format: Format::Pretty("\t".into()),
+        preserve_doctype: true,
+        ..Default::default()
+    };
+    let html = "<!doctype html><div> <p>1 <i>2</i></p><img src=\"x\"><p></p></div>";
+    assert_eq!(
+        html_editor::prettify(html, "\t").unwrap(),
+        parse(html).unwrap().html_with(&options)
+    );
+
+    assert!(html_editor::prettify("<div><p></div>", "  ").is_err());
+}
+
+#[test]
+fn apostrophes() {
+    let nodes = parse(r#"<p title='Rock &apos;n&apos; roll'>Don't &amp; won't</p>"#).unwrap();
+    assert_eq!(
+        nodes.html(),
+        r#"<p title="Rock 'n' roll">Don't &amp; won't</p>"#
+    );
+
+    let xml = SerializeOptions::xml();
+    assert_eq!(
+        nodes.html_with(&xml),
+        r#"<p title="Rock &apos;n&apos; roll">Don&apos;t &amp; won&apos;t</p>"#
+    );
+
+    let html = SerializeOptions {
+        escape_apostrophes: true,
+        ..Default::default()
+    };
+    assert_eq!(nodes.html_with(&html), nodes.html_with(&xml));
+}
+
+#[test]
+fn xml_declaration() {
+    let single = parse("<?xml version='1.0' encoding='utf-8'?><a/>").unwrap();
+    assert_eq!(
+        single.html(),
+        "<?xml version='1.0' encoding='utf-8'?><a></a>"
+    );
+    assert!(matches!(
+        &single[0],
+        Node::Doctype(Doctype::Xml {
+            single_quoted: true,
+            ..
+        })
+    ));
+
+    let double = parse(r#"<?xml version="1.0" encoding="utf-8"?><a/>"#).unwrap();
+    assert_eq!(
+        double.html(),
+        r#"<?xml version="1.0" encoding="utf-8"?><a></a>"#
+    );
+    assert!(matches!((&single[0], &double[0]), (Node::Doctype(a), Node::Doctype(b)) if a != b));
+
+    let without_encoding = parse(r#"<?xml version="1.0"?><a/>"#).unwrap();
+    assert_eq!(without_encoding.html(), r#"<?xml version="1.0"?><a></a>"#);
+    assert!(matches!(
+        &without_encoding[0],
+        Node::Doctype(doctype) if *doctype == Doctype::Xml {
+            version: "1.0".into(),
+            encoding: None,
+            single_quoted: false,
+        }
+    ));
+
+    assert!(parse("<?xml encoding='utf-8'?>").is_err());
+}
+
+#[test]
+fn attribute_escaping() {
+    let node = Node::new_element("a", vec![("title", r#"1 < 2 & "3" > 0"#)], vec![]);
+    let escaped = r#"<a title="1 &lt; 2 &amp; &quot;3&quot; &gt; 0""#;
+    assert_eq!(node.html(), format!("{}></a>", escaped));
+    assert_eq!(
+        node.html_with(&SerializeOptions::xml()),
+        format!("{}/>", escaped)
+    );
+
+    let nodes = parse(r#"<a title='1 < 2 &amp; "3"'></a>"#).unwrap();
+    assert_eq!(
+        nodes.html(),
+        r#"<a title="1 &lt; 2 &amp; &quot;3&quot;"></a>"#
+    );
+    assert_eq!(parse(&nodes.html()).unwrap().html(), nodes.html());
+}
+
+#[test]
+fn empty_attribute_values() {
+    let input = Node::new_element("input", vec![("value", "")], vec![]);
+    assert_eq!(input.html(), r#"<input value="">"#);
+
+    let img = Node::new_element("img", vec![("alt", "")], vec![]);
+    assert_eq!(img.html(), r#"<img alt="">"#);
+    assert_eq!(img.html_with(&SerializeOptions::xml()), r#"<img alt=""/>"#);
+
+    let button = Node::new_element("button", vec![("DISABLED", "")], vec![]);
+    assert_eq!(button.html(), "<button DISABLED></button>");
+    // XML has no bare attribute names
+    assert_eq!(
+        button.html_with(&SerializeOptions::xml()),
+        r#"<button DISABLED="disabled"/>"#
+    );
+
+    let html = r#"<input disabled="" value=""><input disabled value title>"#;
+    assert_eq!(
+        parse(html).unwrap().html(),
+        r#"<input disabled value=""><input disabled value="" title="">"#
+    );
+}
@@ -0,0 +1,84 @@
+use html_editor::operation::*;
+use html_editor::{parse, Element, Node};
+
+fn digest(html: &str) -> u64 {
+    parse(html).unwrap().content_digest()
+}
+
+#[test]
+fn content_digest() {
+    const HTML: &str = r#"<!DOCTYPE html><html><body><div id="a" class="b"><p>Hello <b>World</b></p><pre> x  y </pre></div></body></html>"#;
+    let nodes = parse(HTML).unwrap();
+
+    let pretty = SerializeOptions {
+        format: Format::Pretty("  ".into()),
+        ..Default::default()
+    };
+    assert_eq!(digest(&nodes.html_with(&pretty)), nodes.content_digest());
+    assert_eq!(
+        digest(&HTML.replace("<!DOCTYPE html>", "<!doctype html>")),
+        nodes.content_digest()
+    );
+    assert_eq!(
+        digest(&HTML.replace("Hello ", "Hel<!-- - -->lo\n")),
+        nodes.content_digest()
+    );
+
+    assert_ne!(
+        digest(&HTML.replace("<pre> x  y", "<pre>x  y")),
+        nodes.content_digest()
+    );
+    assert_ne!(
+        digest(&HTML.replace("Hello ", "Hello")),
+        digest(&HTML.replace("Hello ", "Hel lo"))
+    );
+    assert_ne!(
+        digest(&HTML.replace(r#"class="b""#, r#"class="c""#)),
+        nodes.content_digest()
+    );
+    assert_ne!(digest("<p></p><p></p>"), digest("<p><p></p></p>"));
+
+    // Whitespace shows next to inline elements, but not next to blocks
+    assert_ne!(
+        digest("<p>Hello <b>World</b></p>"),
+        digest("<p>Hello<b>World</b></p>")
+    );
+    assert_eq!(
+        digest("<p>Hello  \n<b>World</b></p>"),
+        digest("<p>Hello <b>World</b></p>")
+    );
+    assert_eq!(
+        digest("<div>\n  <p> Hello </p>\n</div>"),
+        digest("<div><p>Hello</p></div>")
+    );
+    assert_eq!(digest("a <br>\n b"), digest("a<br>b"));
+
+    let p = Element::new("p", vec![], vec![Node::Text("Hi".into())]);
+    assert_eq!(p.content_digest(), p.clone().into_node().content_digest());
+    assert_eq!(p.content_digest(), digest("<p>Hi</p>"));
+    // The digest is the same across runs
+    assert_eq!(p.content_digest(), 8718695381477479875);
+}
+
+#[test]
+fn stats() {
+    assert_eq!(Vec::<Node>::new().stats(), TreeStats::default());
+
+    let nodes = parse(r#"<div id="a" class="b"><p>Hello <b>World</b></p>  <br></div><!-- end -->"#)
+        .unwrap();
+    let stats = nodes.stats();
+    assert_eq!(
+        stats,
+        TreeStats {
+            nodes: 8,
+            elements: 4,
+            texts: 3,
+            comments: 1,
+            attributes: 2,
+            max_depth: 4,
+        }
+    );
+    assert_eq!(nodes[0].stats().nodes, 7);
+    assert_eq!(nodes[0].stats(), nodes[0].as_element().unwrap().stats());
+    assert_eq!(nodes[1].stats().max_depth, 1);
+}
@@ -1,5 +1,8 @@
 use html_editor::operation::*;
-use html_editor::{parse, try_parse};
+use html_editor::{
+    parse, parse_with_options, parse_with_warnings, try_parse, try_parse_with_options,
+    InnerHTMLParseError, Node, ParseOptions, ParseWarning,
+};
 
 #[test]
 fn paired_tag() {
@@ -30,6 +33,24 @@ fn attributes() {
     parse("<input disabled type=\"button\">").unwrap();
 }
 
+#[test]
+fn attribute_order() {
+    let html = r#"<a id="first" href="/" id="second" class="a b" class="c">x</a>"#;
+    let nodes = parse(html).unwrap();
+    let a = nodes[0].as_element().unwrap();
+
+    let names: Vec<&str> = a.attrs.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["id", "href", "id", "class", "class"]);
+    assert_eq!(nodes.html(), html);
+
+    // The first of duplicate attributes wins
+    assert_eq!(a.get_attr("id"), Some("first"));
+    assert_eq!(a.attributes_map()["id"], "first");
+    assert!(a.has_attr_value("id", "first"));
+    assert_eq!(a.classes().collect::<Vec<_>>(), vec!["a", "b"]);
+    assert!(Selector::from("#first").matches(a));
+}
+
 #[test]
 fn matched() {
     let a = parse(
@@ -119,3 +140,236 @@ fn xml() {
     .unwrap();
     html.trim();
 }
+
+#[test]
+fn document_structure() {
+    let result = parse_with_warnings("<!DOCTYPE html>\n<html></html>\n").unwrap();
+    assert!(!result.is_fragment());
+
+    let result = parse_with_warnings("Hello").unwrap();
+    assert_eq!(
+        result.warnings,
+        vec![
+            ParseWarning::NoRootElement,
+            ParseWarning::TextOutsideRootElement
+        ]
+    );
+
+    let mut result = parse_with_warnings("<!DOCTYPE html><p>Hello</p> <img>").unwrap();
+    assert_eq!(
+        result.warnings,
+        vec![ParseWarning::MultipleRootElements { count: 2 }]
+    );
+    result.wrap_in_root("body");
+    assert!(result.warnings.is_empty());
+    assert_eq!(
+        result.nodes.html(),
+        "<!DOCTYPE html><body><p>Hello</p> <img></body>"
+    );
+}
+
+#[test]
+fn tag_case() {
+    const HTML: &str = "<DIV><Span>Hi</span><BR><SCRIPT>a < b</SCRIPT></DIV>";
+
+    let nodes = parse(HTML).unwrap();
+    assert_eq!(
+        nodes.html(),
+        "<DIV><Span>Hi</Span><BR><SCRIPT>a < b</SCRIPT></DIV>"
+    );
+    assert_eq!(nodes.query(&Selector::from("div")).unwrap().name, "DIV");
+    assert_eq!(
+        nodes.query_all(&Selector::from("span, br, script")).len(),
+        3
+    );
+
+    let options = ParseOptions {
+        preserve_tag_case: false,
+        ..Default::default()
+    };
+    let nodes = parse_with_options(HTML, &options).unwrap();
+    assert_eq!(
+        nodes.html(),
+        "<div><span>Hi</span><br><script>a < b</script></div>"
+    );
+    assert_eq!(
+        try_parse_with_options("<P>Hi", &options).html(),
+        "<p>Hi</p>"
+    );
+}
+
+#[test]
+fn comment_before_doctype() {
+    const HTML: &str = "<!-- generated -->\n<!DOCTYPE html>\n<html></html>";
+
+    let nodes = parse(HTML).unwrap();
+    assert!(matches!(nodes[0], Node::Comment(ref comment) if comment == " generated "));
+    assert!(matches!(nodes[2], Node::Doctype(..)));
+    assert_eq!(nodes.html(), HTML);
+    assert_eq!(try_parse(HTML).html(), HTML);
+
+    let mut result =
+        parse_with_warnings("<!-- a -->\n<!DOCTYPE html><!-- b --><p>1</p><p>2</p>").unwrap();
+    result.wrap_in_root("body");
+    assert_eq!(
+        result.nodes.html(),
+        "<!-- a -->\n<!DOCTYPE html><body><!-- b --><p>1</p><p>2</p></body>"
+    );
+}
+
+#[test]
+fn root_text() {
+    let nodes = parse("hello <b>world</b>").unwrap();
+    assert!(
+        matches!(nodes.as_slice(), [Node::Text(text), Node::Element(b)] if text == "hello " && b.name == "b")
+    );
+
+    const HTML: &str = "<!DOCTYPE html>\nstray &amp; text<html></html> after";
+    let nodes = parse(HTML).unwrap();
+    assert!(matches!(
+        nodes.as_slice(),
+        [Node::Doctype(..), Node::Text(before), Node::Element(_), Node::Text(after)]
+            if before == "\nstray & text" && after == " after"
+    ));
+    assert_eq!(
+        nodes.html(),
+        "<!DOCTYPE html>\nstray &amp; text<html></html> after"
+    );
+    assert_eq!(try_parse(HTML).html(), nodes.html());
+}
+
+#[test]
+fn trailing_content() {
+    const HTML: &str = "<!DOCTYPE html><html><body>x</body></html>\n<script>track()</script><!-- c -->junk &amp; more\n";
+    let nodes = parse(HTML).unwrap();
+    assert_eq!(nodes.len(), 6);
+    assert!(matches!(&nodes[3], Node::Element(script) if script.name == "script"));
+    assert!(matches!(&nodes[5], Node::Text(text) if text == "junk & more\n"));
+    assert_eq!(nodes.html(), HTML);
+    assert_eq!(try_parse(HTML).html(), HTML);
+
+    // The stray end tag is dropped, and the unclosed element closed
+    let html = "<html><body>x</body></html>\n</div><script>track()</script><p>junk";
+    assert_eq!(
+        try_parse(html).html(),
+        "<html><body>x</body></html>\n<script>track()</script><p>junk</p>"
+    );
+}
+
+#[test]
+fn raw_on_error() {
+    const HTML: &str = r#"<div>1 < > 2<?php if ($a) { ?><b>x</b><?php } ?></div>"#;
+    assert!(matches!(
+        parse(HTML).unwrap_err().inner,
+        InnerHTMLParseError::InvalidTag { .. }
+    ));
+
+    let options = ParseOptions {
+        raw_on_error: true,
+        ..Default::default()
+    };
+    let nodes = parse_with_options(HTML, &options).unwrap();
+    assert!(matches!(
+        nodes[0].children().unwrap(),
+        [Node::Text(_), Node::RawHTML(empty), Node::Text(_), Node::RawHTML(php), Node::Element(_), Node::RawHTML(_)]
+            if empty == "< >" && php == "<?php if ($a) { ?>"
+    ));
+    assert_eq!(nodes.html(), HTML);
+
+    let html = "<div>< ><p>unclosed</div>";
+    assert_eq!(
+        try_parse_with_options(html, &options).html(),
+        "<div>< ><p>unclosed</p></div>"
+    );
+    assert!(try_parse(html).is_empty());
+}
+
+#[test]
+fn raw_on_error_never_panics() {
+    let options = ParseOptions {
+        raw_on_error: true,
+        ..Default::default()
+    };
+    for html in [
+        "<div a=>x</div>",
+        "<div a b='1' c=>x</div>",
+        "<?>",
+        "<?xml version=\"1.0\">",
+    ] {
+        assert!(matches!(
+            parse(html).unwrap_err().inner,
+            InnerHTMLParseError::InvalidTag { .. }
+        ));
+        let nodes = try_parse_with_options(html, &options);
+        assert!(matches!(&nodes[0], Node::RawHTML(_)), "{}", html);
+    }
+    assert_eq!(
+        try_parse_with_options("<div a=>x</div>", &options).html(),
+        "<div a=>x"
+    );
+
+    for html in ["<script>></script>", "<style>></style>", "<!-->-->", "é />"] {
+        assert_eq!(try_parse_with_options(html, &options).html(), html);
+    }
+}
+
+#[test]
+fn multiline_attributes() {
+    let html = "<a\n  href=\"x\"\r\n\tclass=y\x0Cdata-on\n  title='t'\n>link</a><img\n\tsrc=\"a.png\"\n\talt=\"\"\n/>";
+    let nodes = parse(html).unwrap();
+
+    let a = nodes[0].as_element().unwrap();
+    assert_eq!(a.name, "a");
+    assert_eq!(a.attrs.len(), 4);
+    assert_eq!(a.get_attr("href"), Some("x"));
+    assert_eq!(a.get_attr("class"), Some("y"));
+    assert_eq!(a.get_attr("data-on"), Some(""));
+    assert_eq!(a.get_attr("title"), Some("t"));
+
+    let img = nodes[1].as_element().unwrap();
+    assert_eq!(img.name, "img");
+    assert_eq!(img.get_attr("src"), Some("a.png"));
+    assert_eq!(img.get_attr("alt"), Some(""));
+}
+
+#[test]
+fn cdata() {
+    let html = "<p><![CDATA[a < b]]></p><svg><![CDATA[<c>]]></svg><math></math><![CDATA[d]]>";
+    let xml = ParseOptions {
+        xml: true,
+        ..Default::default()
+    };
+
+    let nodes = parse(html).unwrap();
+    let p = nodes[0].children().unwrap();
+    assert!(matches!(&p[0], Node::Comment(comment) if comment == "[CDATA[a < b]]"));
+    let svg = nodes[1].children().unwrap();
+    assert!(matches!(&svg[0], Node::CData(text) if text == "<c>"));
+    // Foreign content ends with the `<svg>` and `<math>`
+    assert!(matches!(&nodes[3], Node::Comment(comment) if comment == "[CDATA[d]]"));
+    assert_eq!(
+        nodes.html(),
+        "<p><!--[CDATA[a < b]]--></p><svg><![CDATA[<c>]]></svg><math></math><!--[CDATA[d]]-->"
+    );
+
+    let nodes = parse_with_options(html, &xml).unwrap();
+    let p = nodes[0].children().unwrap();
+    assert!(matches!(&p[0], Node::CData(text) if text == "a < b"));
+    assert!(matches!(&nodes[3], Node::CData(text) if text == "d"));
+    assert_eq!(nodes.html(), html);
+
+    // A section which can't be a comment is left as is
+    let html = "<p><![CDATA[a-->b]]></p>";
+    let nodes = parse(html).unwrap();
+    let p = nodes[0].children().unwrap();
+    assert!(matches!(&p[0], Node::CData(text) if text == "a-->b"));
+    assert_eq!(nodes.html(), html);
+    assert_eq!(parse(&nodes.html()).unwrap().html(), html);
+
+    // The sections are text
+    let mut nodes = parse("<svg><text><![CDATA[hi]]></text></svg>").unwrap();
+    assert_eq!(nodes[0].as_element().unwrap().inner_text(), "hi");
+    assert_eq!(nodes.stats().texts, 1);
+    nodes.map_text(|text| text.to_uppercase());
+    assert_eq!(nodes.html(), "<svg><text><![CDATA[HI]]></text></svg>");
+}
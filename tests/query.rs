@@ -1,5 +1,5 @@
 use html_editor::operation::*;
-use html_editor::parse;
+use html_editor::{parse, Node};
 
 const HTML: &str = r#"
     <div>
@@ -54,3 +54,370 @@ fn html_editor_multiple_class_parsing() {
     let selector = Selector::from(".a");
     nodes.query(&selector).unwrap();
 }
+
+#[test]
+fn template_content() {
+    let html = r#"<div><span>Visible</span><template><span>Inert</span></template></div>"#;
+    let mut nodes = parse(html).unwrap();
+    let selector = Selector::from("span");
+
+    assert_eq!(nodes.query_all(&selector).len(), 1);
+    assert!(nodes.query(&Selector::from("template")).is_some());
+
+    let options = QueryOptions {
+        into_templates: true,
+    };
+    assert_eq!(nodes.query_all_with(&selector, &options).len(), 2);
+
+    // `execute_for` skips the content the same way
+    let mut count = 0;
+    nodes.execute_for(&selector, |_| count += 1);
+    assert_eq!(count, 1);
+    nodes.execute_for_with(&selector, &options, |span| span.children.clear());
+    assert_eq!(
+        nodes.html(),
+        "<div><span></span><template><span></span></template></div>"
+    );
+}
+
+#[test]
+fn descendant_query() {
+    let html = r#"
+        <section>
+            <p class="a">1</p>
+            <div><span class="b">2</span></div>
+            <p class="c">3</p>
+        </section>
+        <p class="a">4</p>"#;
+    let nodes = parse(html).unwrap();
+
+    let texts = |selector: &str| {
+        nodes
+            .query_all(&Selector::from(selector))
+            .iter()
+            .map(|element| element.children.html())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(texts("section :is(.a, .b)"), vec!["1", "2"]);
+    assert_eq!(texts(":where(section) :where(.a)"), vec!["1"]);
+    assert_eq!(texts("section div span"), vec!["2"]);
+    assert_eq!(texts("div .a"), Vec::<String>::new());
+    assert_eq!(texts(".a"), vec!["1", "4"]);
+}
+
+#[test]
+fn find_first_mut() {
+    let html = r#"<ul><li>1</li><li data-id="a">2</li></ul><p data-id="b">3</p>"#;
+    let mut nodes = parse(html).unwrap();
+
+    let element = nodes
+        .find_first_mut(|element| element.attrs.iter().any(|(k, _)| k == "data-id"))
+        .unwrap();
+    element.attrs[0].1 = "first".to_string();
+    assert_eq!(
+        nodes.html(),
+        r#"<ul><li>1</li><li data-id="first">2</li></ul><p data-id="b">3</p>"#
+    );
+
+    assert!(nodes
+        .find_first_mut(|element| element.name == "table")
+        .is_none());
+}
+
+#[test]
+fn query_all_id() {
+    let html = r#"<div id="a">1</div><div><b id="a">2</b></div>"#;
+    let nodes = parse(html).unwrap();
+
+    let first = nodes.query_all(&Selector::from("#a"));
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].name, "div");
+
+    assert_eq!(nodes.query_all(&Selector::from("#a, b")).len(), 2);
+    assert_eq!(nodes.query_all(&Selector::from("#b")).len(), 0);
+}
+
+#[test]
+fn comments() {
+    let html = r#"<!--a--><div><!--b--><template><!--c--></template></div><p>x</p><!--d-->"#;
+    let mut nodes = parse(html).unwrap();
+    assert_eq!(
+        nodes.comments().collect::<Vec<_>>(),
+        vec!["a", "b", "c", "d"]
+    );
+    assert_eq!(nodes[1].comments().collect::<Vec<_>>(), vec!["b", "c"]);
+    assert_eq!(nodes[3].comments().collect::<Vec<_>>(), vec!["d"]);
+
+    for comment in nodes.comments_mut() {
+        comment.make_ascii_uppercase();
+    }
+    assert_eq!(
+        nodes.html(),
+        r#"<!--A--><div><!--B--><template><!--C--></template></div><p>x</p><!--D-->"#
+    );
+}
+
+#[test]
+fn root_query() {
+    let html = r#"<!DOCTYPE html><html><body><div><body></body></div></body></html>"#;
+    let nodes = parse(html).unwrap();
+
+    let bodies = nodes.query_all(&Selector::from(":root > body"));
+    assert_eq!(bodies.len(), 1);
+    assert_eq!(bodies[0].children.len(), 1);
+
+    assert_eq!(nodes.query_all(&Selector::from(":root")).len(), 1);
+    assert_eq!(nodes.query_all(&Selector::from("div > body")).len(), 1);
+    assert_eq!(nodes.query_all(&Selector::from("html > div")).len(), 0);
+
+    let fragment = parse("<p>1</p><p>2<p>3</p></p>").unwrap();
+    assert_eq!(fragment.query_all(&Selector::from(":root")).len(), 2);
+}
+
+#[test]
+fn query_iter() {
+    let html = r#"<div id="1"><div id="2"><p><div id="3"></div></p></div></div><div id="4"></div>"#;
+    let nodes = parse(html).unwrap();
+    let ids = |elements: Vec<&html_editor::Element>| {
+        elements
+            .iter()
+            .map(|element| element.attrs[0].1.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let selector = Selector::from("div");
+    assert_eq!(
+        ids(nodes.query_iter(&selector).collect()),
+        vec!["1", "2", "3", "4"]
+    );
+    assert_eq!(ids(nodes.query_all(&selector)), vec!["1", "2", "3", "4"]);
+    assert_eq!(
+        ids(nodes.query_iter(&selector).skip(1).take(2).collect()),
+        vec!["2", "3"]
+    );
+
+    let selector = Selector::from("div > p > div, :root");
+    assert_eq!(
+        ids(nodes.query_iter(&selector).collect()),
+        vec!["1", "3", "4"]
+    );
+    assert_eq!(
+        ids(nodes[0].query_iter(&selector).collect()),
+        vec!["1", "3"]
+    );
+}
+
+#[test]
+fn nth_of_type() {
+    let html = r#"<section><p>1</p><div>a</div><div>b</div><p>2</p><!-- c --><div>c</div><p>3</p>text<p>4</p></section>"#;
+    let nodes = parse(html).unwrap();
+    let query = |selector: &str| {
+        let selector = Selector::from(selector);
+        let htmls: Vec<String> = nodes
+            .query_all(&selector)
+            .iter()
+            .map(|e| e.html())
+            .collect();
+        assert_eq!(
+            nodes.query(&selector).map(|e| e.html()),
+            htmls.first().cloned()
+        );
+        htmls
+    };
+
+    assert_eq!(query("p:nth-of-type(2)"), vec!["<p>2</p>"]);
+    assert_eq!(query("p:first-of-type"), vec!["<p>1</p>"]);
+    assert_eq!(query("p:last-of-type"), vec!["<p>4</p>"]);
+    assert_eq!(
+        query("div:first-of-type, div:last-of-type"),
+        vec!["<div>a</div>", "<div>c</div>"]
+    );
+    assert_eq!(query("p:nth-of-type(odd)"), vec!["<p>1</p>", "<p>3</p>"]);
+    assert_eq!(query("p:nth-of-type(-n + 2)"), vec!["<p>1</p>", "<p>2</p>"]);
+    assert_eq!(query("p:nth-last-of-type(2)"), vec!["<p>3</p>"]);
+    assert_eq!(
+        query("section:first-of-type > p:nth-of-type(2n)"),
+        vec!["<p>2</p>", "<p>4</p>"]
+    );
+    assert!(query("p:nth-of-type(2n+)").is_empty());
+
+    let mut nodes = parse(html).unwrap();
+    nodes.execute_for(&Selector::from("p:nth-of-type(2)"), |p| p.children.clear());
+    nodes
+        .query_mut(&Selector::from("div:nth-last-of-type(3)"))
+        .unwrap()
+        .children
+        .clear();
+    nodes.remove_by(&Selector::from(
+        "div:nth-of-type(2), section > :last-of-type",
+    ));
+    assert_eq!(
+        nodes.html(),
+        "<section><p>1</p><div></div><p></p><!-- c --><p>3</p>text</section>"
+    );
+}
+
+#[test]
+fn mutable_queries_in_element() {
+    let mut div = parse("<div><p>1</p><section><p>2</p><p>3</p></section></div>")
+        .unwrap()
+        .remove(0);
+    div.execute_for(&Selector::from("div section > p:last-child"), |p| {
+        p.children.clear()
+    });
+    div.query_mut(&Selector::from("section p:first-child"))
+        .unwrap()
+        .attrs
+        .push(("id".into(), "x".into()));
+    div.query_mut(&Selector::from("div"))
+        .unwrap()
+        .attrs
+        .push(("class".into(), "y".into()));
+    assert_eq!(
+        div.html(),
+        r#"<div class="y"><p>1</p><section><p id="x">2</p><p></p></section></div>"#
+    );
+}
+
+#[test]
+fn query_selector() {
+    let nodes = parse(HTML).unwrap();
+    assert_eq!(
+        nodes.query_selector("div.last").unwrap().unwrap().html(),
+        r#"<div class="last">Last Element</div>"#
+    );
+    assert_eq!(nodes.query_selector_all("div > span").unwrap().len(), 2);
+    assert_eq!(nodes[1].query_selector_all("span").unwrap().len(), 2);
+    assert!(nodes.query_selector("p").unwrap().is_none());
+    assert_eq!(
+        nodes.query_selector_all("span:").unwrap_err(),
+        SelectorParseError::UnexpectedEnd
+    );
+}
+
+#[test]
+fn query_all_paths() {
+    let html = r#"<div><p>a</p><template><p>b</p></template><section><p>c</p></section></div>"#;
+    let mut nodes = parse(html).unwrap();
+    let selector = Selector::from("p");
+
+    let paths = nodes.query_all_paths(&selector);
+    assert_eq!(paths, vec![vec![0, 0], vec![0, 2, 0]]);
+    let options = QueryOptions {
+        into_templates: true,
+    };
+    let template_paths = nodes.query_all_paths_with(&selector, &options);
+    assert_eq!(
+        template_paths,
+        vec![vec![0, 0], vec![0, 1, 0], vec![0, 2, 0]]
+    );
+    for (path, p) in template_paths
+        .iter()
+        .zip(nodes.query_all_with(&selector, &options))
+    {
+        assert!(std::ptr::eq(nodes.element_at(path).unwrap(), p));
+    }
+    for path in &paths {
+        let node = nodes.node_at(path).unwrap();
+        assert_eq!(node.as_element().unwrap().name, "p");
+    }
+
+    // An element or a node has the empty path itself, like it is part of
+    // `query_all`
+    let div = nodes[0].as_element().unwrap();
+    let all_selector = Selector::from("div, p");
+    assert_eq!(div.query_all(&all_selector).len(), 3);
+    assert_eq!(
+        div.query_all_paths(&all_selector),
+        vec![vec![], vec![0], vec![2, 0]]
+    );
+    assert!(std::ptr::eq(div.element_at(&[]).unwrap(), div));
+    assert_eq!(
+        nodes[0].query_all_paths(&Selector::from("div")),
+        vec![NodePath::new()]
+    );
+    assert!(std::ptr::eq(nodes[0].node_at(&[]).unwrap(), &nodes[0]));
+    assert!(std::ptr::eq(nodes[0].element_at(&[]).unwrap(), div));
+    // A node is queried as its element, which is treated as an only child
+    for selector in ["div:first-child", "div:last-of-type", "div:nth-child(2)"] {
+        let selector = Selector::from(selector);
+        assert_eq!(nodes[0].query_all(&selector), div.query_all(&selector));
+        assert_eq!(
+            nodes[0].query_all_paths(&selector),
+            div.query_all_paths(&selector)
+        );
+    }
+
+    // Only the first element is found for an id, like in `query_all`
+    let ids = parse(r#"<p id="a"></p><div><p id="a"></p></div><p id="a"></p>"#).unwrap();
+    let id = Selector::from("#a");
+    assert_eq!(ids.query_all(&id).len(), 1);
+    assert_eq!(ids.query_all_paths(&id), vec![vec![0]]);
+    assert_eq!(ids[1].query_all_paths(&id), vec![vec![0]]);
+
+    for path in paths.iter().rev() {
+        let (index, parent) = path.split_last().unwrap();
+        if let Some(Node::Element(parent)) = nodes.node_at_mut(parent) {
+            parent.children.remove(*index);
+        }
+    }
+    assert_eq!(
+        nodes.html(),
+        "<div><template><p>b</p></template><section></section></div>"
+    );
+
+    assert!(nodes.node_at(&[]).is_none());
+    assert!(nodes.node_at(&[0, 5]).is_none());
+    assert!(nodes.node_at_mut(&[0, 1, 0]).is_none());
+}
+
+#[test]
+fn nth_child() {
+    // Text and comments are not counted, only the elements
+    let html = "<ul><li>1</li><li>2</li>text<li>3</li><!-- c --><li>4</li><li>5</li><p>6</p></ul>";
+    let nodes = parse(html).unwrap();
+    let positions = |selector: &str| -> Vec<String> {
+        let selector = Selector::parse(&format!("ul > {}", selector)).unwrap();
+        nodes
+            .query_all(&selector)
+            .iter()
+            .map(|e| e.inner_text())
+            .collect()
+    };
+
+    for (selector, expected) in [
+        (":nth-child(2n)", vec!["2", "4", "6"]),
+        (":nth-child(2n+1)", vec!["1", "3", "5"]),
+        (":nth-child(odd)", vec!["1", "3", "5"]),
+        (":nth-child(EVEN)", vec!["2", "4", "6"]),
+        (":nth-child(3)", vec!["3"]),
+        (":nth-child(+3)", vec!["3"]),
+        (":nth-child(0n+2)", vec!["2"]),
+        (":nth-child(-n+3)", vec!["1", "2", "3"]),
+        (":nth-child(-2n + 5)", vec!["1", "3", "5"]),
+        (":nth-child(n+4)", vec!["4", "5", "6"]),
+        (":nth-child(3n-1)", vec!["2", "5"]),
+        (":nth-child(-n-1)", vec![]),
+        (":nth-child(0)", vec![]),
+        (":nth-child(-1)", vec![]),
+        (":nth-last-child(2n)", vec!["1", "3", "5"]),
+        (":nth-last-child(-n+2)", vec!["5", "6"]),
+        ("li:first-child", vec!["1"]),
+        ("li:last-child", vec![]),
+        (":last-child", vec!["6"]),
+    ] {
+        assert_eq!(positions(selector), expected, "{}", selector);
+    }
+    // An element without siblings is both the first and the last child
+    let only = Selector::from("ul:first-child:last-child, ul:nth-child(-n+1):nth-last-child(1)");
+    assert_eq!(nodes.query_all(&only).len(), 1);
+
+    for invalid in [
+        ":nth-child(2n+)",
+        ":nth-child(n2)",
+        ":nth-child(2 n)",
+        ":nth-child()",
+    ] {
+        assert!(Selector::parse(invalid).is_err(), "{}", invalid);
+    }
+}
@@ -43,3 +43,120 @@ fn complex() {
         r#"Selector([CompoundSelector([Tag("h1")]), CompoundSelector([Tag("h2")])])"#
     );
 }
+
+#[test]
+fn descendant() {
+    assert_eq!(
+        format!("{:?}", Selector::from("section  p")),
+        r#"Selector([CompoundSelector([Tag("p"), DescendantOf(CompoundSelector([Tag("section")]))])])"#
+    );
+    assert_eq!(
+        format!("{:?}", Selector::from("article :is(h1, .title)")),
+        r#"Selector([CompoundSelector([Is(Selector([CompoundSelector([Tag("h1")]), CompoundSelector([Class("title")])])), DescendantOf(CompoundSelector([Tag("article")]))])])"#
+    );
+}
+
+#[test]
+fn child() {
+    assert_eq!(
+        format!("{:?}", Selector::from(":root>body")),
+        r#"Selector([CompoundSelector([Tag("body"), ChildOf(CompoundSelector([Root]))])])"#
+    );
+    assert_eq!(
+        format!("{:?}", Selector::from("ul > li a")),
+        r#"Selector([CompoundSelector([Tag("a"), DescendantOf(CompoundSelector([Tag("li"), ChildOf(CompoundSelector([Tag("ul")]))]))])])"#
+    );
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(
+        Selector::parse("").unwrap_err(),
+        SelectorParseError::UnexpectedEnd
+    );
+    assert_eq!(
+        Selector::parse("ul >").unwrap_err(),
+        SelectorParseError::UnexpectedEnd
+    );
+    assert_eq!(
+        Selector::parse("h1 > > p").unwrap_err(),
+        SelectorParseError::UnexpectedChar('>')
+    );
+    assert_eq!(
+        Selector::parse("a[href").unwrap_err(),
+        SelectorParseError::UnexpectedEnd
+    );
+    for (selector, ch) in [
+        ("a{b}", '{'),
+        ("p;", ';'),
+        ("a/b", '/'),
+        ("div!", '!'),
+        ("@media", '@'),
+        (".a%", '%'),
+    ] {
+        assert_eq!(
+            Selector::parse(selector).unwrap_err(),
+            SelectorParseError::UnexpectedChar(ch)
+        );
+    }
+    assert!(Selector::parse(r"a\{b\}").is_ok());
+    assert!(Selector::parse("section-2 .é_x").is_ok());
+    assert_eq!(
+        Selector::parse(":hovering").unwrap_err(),
+        SelectorParseError::UnknownPseudoClass("hovering".to_string())
+    );
+    assert_eq!(
+        "li:nth-of-type(2x)".parse::<Selector>().unwrap_err(),
+        SelectorParseError::InvalidNth("2x".to_string())
+    );
+    assert_eq!(
+        format!("{:?}", "div#app".parse::<Selector>().unwrap()),
+        format!("{:?}", Selector::from("div#app"))
+    );
+}
+
+#[test]
+fn attribute() {
+    assert_eq!(
+        format!(
+            "{:?}",
+            Selector::from(r#"input[ type = "a\"b" i ][disabled]"#)
+        ),
+        r#"Selector([CompoundSelector([Tag("input"), Attr { name: "type", value: Some("a\"b"), ignore_case: true }, Attr { name: "disabled", value: None, ignore_case: false }])])"#
+    );
+
+    let nodes = html_editor::parse(
+        r#"<input type="Checkbox" DISABLED><input type="text"><a href="" target='_blank'></a>"#,
+    )
+    .unwrap();
+    let count = |selector: &str| nodes.query_all(&Selector::parse(selector).unwrap()).len();
+    assert_eq!(count("[disabled]"), 1);
+    assert_eq!(count("[type]"), 2);
+    assert_eq!(count("[type=checkbox]"), 0);
+    assert_eq!(count("[type=checkbox i]"), 1);
+    assert_eq!(count("[type='Checkbox' s]"), 1);
+    assert_eq!(count("a[href][target=_blank]"), 1);
+    assert_eq!(count(r#"[href=""]"#), 1);
+}
+
+#[test]
+fn unsupported() {
+    for (selector, feature) in [
+        ("a:hover", ":hover"),
+        ("li:NOT(.active)", ":NOT"),
+        ("p:only-child", ":only-child"),
+        ("p::before", "::before"),
+        ("h1 + p", "+"),
+        ("h1~p", "~"),
+        ("ul > *", "*"),
+        ("a[href^=https]", "^="),
+        ("[lang|=en]", "|="),
+    ] {
+        assert_eq!(
+            Selector::parse(selector).unwrap_err(),
+            SelectorParseError::Unsupported(feature.to_string()),
+            "{}",
+            selector
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use std::ops::ControlFlow;
+
+use html_editor::operation::*;
+use html_editor::{parse, select_streaming, InnerHTMLParseError};
+
+const HTML: &str = r#"
+    <!DOCTYPE html>
+    <html>
+    <body>
+        <nav><a href="/">Home</a><a class="active" href="/docs">Docs</a></nav>
+        <main id="content">
+            <section><p>One <b>bold</b></p><br><p data-n="2">Two</p></section>
+            <section><div><p>Three</p></div><img src="a.png"/></section>
+        </main>
+    </body>
+    </html>"#;
+
+fn streamed(selector: &str) -> Vec<String> {
+    let mut htmls = Vec::new();
+    select_streaming(HTML, &Selector::from(selector), |element| {
+        htmls.push(element.html());
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    htmls
+}
+
+#[test]
+fn same_as_query_all() {
+    let nodes = parse(HTML).unwrap();
+    for selector in [
+        "a",
+        "a.active",
+        "#content p",
+        "section > p",
+        "p[data-n=2]",
+        "main :is(br, img)",
+        "section:first-of-type p:nth-of-type(2)",
+        "section > :nth-child(odd)",
+        "nav > :first-child",
+        "body > :root",
+        ":root",
+        "b",
+    ] {
+        let queried: Vec<String> = nodes
+            .query_all(&Selector::from(selector))
+            .iter()
+            .map(|element| element.html())
+            .collect();
+        assert_eq!(streamed(selector), queried, "{}", selector);
+    }
+}
+
+#[test]
+fn nested_matches() {
+    // An element is given once it ends, after the ones inside it
+    assert_eq!(
+        streamed("section, div"),
+        vec![
+            r#"<section><p>One <b>bold</b></p><br><p data-n="2">Two</p></section>"#,
+            "<div><p>Three</p></div>",
+            r#"<section><div><p>Three</p></div><img src="a.png"></section>"#,
+        ]
+    );
+    // The following siblings are not known yet
+    assert!(streamed("p:last-of-type").is_empty());
+    assert!(streamed("a:last-child").is_empty());
+}
+
+#[test]
+fn stop_and_errors() {
+    let mut count = 0;
+    let html = "<p>1</p><p>2</p><div>";
+    let result = select_streaming(html, &Selector::from("p"), |_| {
+        count += 1;
+        ControlFlow::Break(())
+    });
+    assert_eq!(result, Ok(()));
+    assert_eq!(count, 1);
+
+    let result = select_streaming(html, &Selector::from("p"), |_| ControlFlow::Continue(()));
+    assert!(
+        matches!(result.unwrap_err().inner, InnerHTMLParseError::UnclosedTag { tag } if tag == "div")
+    );
+
+    let result = select_streaming("<p></div>", &Selector::from("p"), |_| {
+        ControlFlow::Continue(())
+    });
+    assert_eq!(result, Err(parse("<p></div>").unwrap_err()));
+}